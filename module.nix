@@ -36,6 +36,15 @@ in {
         Enable metasearch logging. Does not affect stderr.
       '';
     };
+    environmentFile = lib.mkOption {
+      type = lib.types.nullOr lib.types.path;
+      default = null;
+      description = ''
+        Optional file with `METASEARCH__` environment variables that override the settings,
+        for secrets that shouldn't be in the Nix store.
+      '';
+      example = "/run/secrets/metasearch.env";
+    };
     settings = lib.mkOption {
       type = lib.types.attrs;
       default = {};
@@ -65,6 +74,7 @@ in {
       description = "a cute metasearch engine";
       serviceConfig = {
         ExecStart = "${self.packages.${pkgs.system}.default}/bin/metasearch" + settingArg + loggingArg;
+        EnvironmentFile = lib.mkIf (cfg.environmentFile != null) cfg.environmentFile;
       };
     };
 
@@ -0,0 +1,29 @@
+//! Subcommands that are run from the command line instead of starting the
+//! server.
+
+use std::path::PathBuf;
+
+use eyre::eyre;
+
+use crate::config::Config;
+
+pub mod check_engines;
+pub mod config;
+pub mod search;
+
+/// Get the value that comes after a flag like `--engine`.
+fn flag_value(args: &mut impl Iterator<Item = String>, flag: &str) -> eyre::Result<String> {
+    args.next().ok_or_else(|| eyre!("{flag} needs a value"))
+}
+
+/// Read the config from `--config`, or from the same place that the server
+/// reads it from. If there's no config there, the defaults are used without
+/// writing a new file.
+fn load_config(config_path: Option<PathBuf>) -> eyre::Result<Config> {
+    let config_path = crate::config_path(config_path);
+    if config_path.exists() {
+        Config::read(&config_path)
+    } else {
+        Ok(Config::default())
+    }
+}
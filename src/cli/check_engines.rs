@@ -0,0 +1,308 @@
+//! `metasearch check-engines`, which makes sure that the engines are still
+//! returning results that we can parse.
+
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use eyre::bail;
+use futures::future::join_all;
+use serde::Serialize;
+
+use crate::{
+    config::Config,
+    engines::{
+        fixtures::{Fixture, FixtureKind},
+        Engine,
+    },
+};
+
+use super::{flag_value, load_config};
+
+#[derive(Debug, Default)]
+struct Args {
+    engines: Vec<String>,
+    /// Parse the fixtures in this directory instead of making requests.
+    fixtures: Option<PathBuf>,
+    json: bool,
+    config_path: Option<PathBuf>,
+}
+
+impl Args {
+    fn parse(args: impl IntoIterator<Item = String>) -> eyre::Result<Self> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--engine" => parsed.engines.push(flag_value(&mut args, &arg)?),
+                "--fixtures" => parsed.fixtures = Some(flag_value(&mut args, &arg)?.into()),
+                "--format" => {
+                    parsed.json = match flag_value(&mut args, &arg)?.as_str() {
+                        "table" => false,
+                        "json" => true,
+                        format => bail!("unknown format {format:?}, expected table or json"),
+                    }
+                }
+                "--config" => parsed.config_path = Some(flag_value(&mut args, &arg)?.into()),
+                _ => bail!("unknown argument {arg:?}"),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// The queries that each engine is checked with. Post-search engines are
+/// checked with the url of a page that they'd show an infobox for.
+fn canaries(engine: &Engine) -> &'static [(FixtureKind, &'static str)] {
+    use FixtureKind::*;
+
+    match engine {
+        Engine::Google => &[
+            (All, "rust programming language"),
+            (Images, "cat"),
+            (News, "weather"),
+        ],
+        Engine::Bing => &[
+            (All, "rust programming language"),
+            (Images, "cat"),
+            (News, "weather"),
+            (Videos, "rust tutorial"),
+        ],
+        Engine::Brave => &[
+            (All, "rust programming language"),
+            (News, "weather"),
+            (Videos, "rust tutorial"),
+        ],
+        Engine::GoogleScholar => &[(All, "attention is all you need")],
+        Engine::Marginalia
+        | Engine::RightDao
+        | Engine::Stract
+        | Engine::Yep
+        | Engine::Wikipedia
+        | Engine::Custom(_) => &[(All, "rust programming language")],
+        Engine::Invidious => &[(Videos, "rust tutorial")],
+        Engine::Dictionary => &[(All, "define hello")],
+        Engine::Thesaurus => &[(All, "synonyms for happy")],
+        Engine::DocsRs => &[(PostSearch, "https://docs.rs/tokio/latest/tokio/")],
+        Engine::GitHub => &[(PostSearch, "https://github.com/rust-lang/rust")],
+        Engine::Mdn => &[(
+            PostSearch,
+            "https://developer.mozilla.org/en-US/docs/Web/HTML/Element/div",
+        )],
+        Engine::MinecraftWiki => &[(PostSearch, "https://minecraft.wiki/w/Diamond")],
+        Engine::StackExchange => &[(
+            PostSearch,
+            "https://stackoverflow.com/questions/927358/how-do-i-undo-the-most-recent-local-commits-in-git",
+        )],
+        // the other engines don't make requests
+        _ => &[],
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CheckStatus {
+    Ok,
+    /// The engine responded and the response was parsed, but there weren't
+    /// any results. This usually means that the engine changed its markup.
+    Empty,
+    Error,
+}
+
+#[derive(Debug, Serialize)]
+struct CheckResult {
+    engine: String,
+    kind: FixtureKind,
+    query: String,
+    status: CheckStatus,
+    results: usize,
+    /// How long the request took. This is missing when checking fixtures.
+    latency_ms: Option<u64>,
+    error: Option<String>,
+}
+
+impl CheckResult {
+    fn new(fixture: &Fixture, latency: Option<Duration>, config: Arc<Config>) -> Self {
+        let (status, results, error) = match fixture.parse(config) {
+            Ok(parsed) => {
+                let results = count_results(&parsed);
+                let status = if results == 0 {
+                    CheckStatus::Empty
+                } else {
+                    CheckStatus::Ok
+                };
+                (status, results, None)
+            }
+            Err(e) => (CheckStatus::Error, 0, Some(e.to_string())),
+        };
+        Self {
+            engine: fixture.engine.id().to_string(),
+            kind: fixture.kind,
+            query: fixture.query.clone(),
+            status,
+            results,
+            latency_ms: latency.map(|latency| latency.as_millis() as u64),
+            error,
+        }
+    }
+
+    fn error(fixture: &Fixture, latency: Duration, error: String) -> Self {
+        Self {
+            engine: fixture.engine.id().to_string(),
+            kind: fixture.kind,
+            query: fixture.query.clone(),
+            status: CheckStatus::Error,
+            results: 0,
+            latency_ms: Some(latency.as_millis() as u64),
+            error: Some(error),
+        }
+    }
+}
+
+/// The number of results in a parsed response, counting answers and infoboxes
+/// as one result each.
+fn count_results(parsed: &serde_json::Value) -> usize {
+    let Some(parsed) = parsed.as_object() else {
+        return 0;
+    };
+    parsed
+        .values()
+        .map(|value| match value {
+            serde_json::Value::Array(results) => results.len(),
+            serde_json::Value::Null => 0,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Run the checks and print the results. Returns whether every check passed.
+pub async fn run(args: impl IntoIterator<Item = String>) -> eyre::Result<bool> {
+    let args = Args::parse(args)?;
+    let config = Arc::new(load_config(args.config_path)?);
+
+    let mut engines = Vec::new();
+    for id in &args.engines {
+        let Some(engine) = Engine::all_with_custom(&config)
+            .into_iter()
+            .find(|engine| engine.id() == id)
+        else {
+            bail!("unknown engine {id:?}");
+        };
+        engines.push(engine);
+    }
+
+    let results = match &args.fixtures {
+        Some(dir) => {
+            let mut results = Vec::new();
+            for (_, fixture) in Fixture::load_dir(dir)? {
+                if engines.is_empty() || engines.contains(&fixture.engine) {
+                    results.push(CheckResult::new(&fixture, None, config.clone()));
+                }
+            }
+            results
+        }
+        None => {
+            if engines.is_empty() {
+                // only check the engines that would actually be used
+                engines = Engine::all_with_custom(&config)
+                    .into_iter()
+                    .filter(|engine| config.engines.get(engine).enabled)
+                    .collect();
+            }
+            check_live(&engines, &config).await
+        }
+    };
+
+    if results.is_empty() {
+        bail!("there was nothing to check");
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        print_table(&results);
+    }
+
+    Ok(results
+        .iter()
+        .all(|result| result.status == CheckStatus::Ok))
+}
+
+async fn check_live(engines: &[Engine], config: &Arc<Config>) -> Vec<CheckResult> {
+    let mut checks = Vec::new();
+    for engine in engines {
+        for &(kind, query) in canaries(engine) {
+            let canary = Fixture {
+                engine: engine.clone(),
+                kind,
+                query: query.to_string(),
+                page: 1,
+                // post-search engines request the page directly
+                url: if kind == FixtureKind::PostSearch {
+                    query.to_string()
+                } else {
+                    String::new()
+                },
+                status: 0,
+                headers: Vec::new(),
+                body: String::new(),
+            };
+            checks.push(async move {
+                let start_time = Instant::now();
+                match canary.rerecord(config.clone()).await {
+                    Ok(fixture) => {
+                        CheckResult::new(&fixture, Some(start_time.elapsed()), config.clone())
+                    }
+                    Err(e) => CheckResult::error(&canary, start_time.elapsed(), e.to_string()),
+                }
+            });
+        }
+    }
+    join_all(checks).await
+}
+
+fn print_table(results: &[CheckResult]) {
+    let rows = results
+        .iter()
+        .map(|result| {
+            let status = match result.status {
+                CheckStatus::Ok => "ok".to_string(),
+                CheckStatus::Empty => "empty".to_string(),
+                CheckStatus::Error => {
+                    format!("error: {}", result.error.as_deref().unwrap_or_default())
+                }
+            };
+            [
+                result.engine.clone(),
+                result.kind.to_string(),
+                result.query.clone(),
+                result.results.to_string(),
+                match result.latency_ms {
+                    Some(latency_ms) => format!("{latency_ms}ms"),
+                    None => "-".to_string(),
+                },
+                status,
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let header = ["ENGINE", "KIND", "QUERY", "RESULTS", "LATENCY", "STATUS"].map(String::from);
+    let mut widths = header.clone().map(|column| column.chars().count());
+    for row in &rows {
+        for (width, column) in widths.iter_mut().zip(row) {
+            *width = (*width).max(column.chars().count());
+        }
+    }
+
+    for row in [header].iter().chain(&rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(column, width)| format!("{column:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    }
+}
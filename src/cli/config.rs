@@ -0,0 +1,71 @@
+//! `metasearch config`, for checking the config and printing the effective
+//! config without starting the server.
+
+use std::path::PathBuf;
+
+use eyre::bail;
+
+use crate::config::Config;
+
+use super::{flag_value, load_config};
+
+#[derive(Debug, Default)]
+struct Args {
+    config_path: Option<PathBuf>,
+    /// Print secrets like the status token in `config dump` instead of
+    /// redacting them.
+    show_secrets: bool,
+}
+
+impl Args {
+    fn parse(args: impl IntoIterator<Item = String>) -> eyre::Result<Self> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => parsed.config_path = Some(flag_value(&mut args, &arg)?.into()),
+                "--show-secrets" => parsed.show_secrets = true,
+                _ => bail!("unknown argument {arg:?}"),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// Run a `config` subcommand. Returns whether the config is valid.
+pub async fn run(args: impl IntoIterator<Item = String>) -> eyre::Result<bool> {
+    let mut args = args.into_iter();
+    match args.next().as_deref() {
+        Some("check") => check(Args::parse(args)?),
+        Some("dump") => dump(Args::parse(args)?),
+        Some(subcommand) => {
+            bail!("unknown config subcommand {subcommand:?}, expected check or dump")
+        }
+        None => bail!("expected a config subcommand (check or dump)"),
+    }
+}
+
+/// Print every problem in the config.
+fn check(args: Args) -> eyre::Result<bool> {
+    let config_path = crate::config_path(args.config_path);
+    if !config_path.exists() {
+        bail!("there's no config at {config_path:?}");
+    }
+
+    let (_, problems) = Config::check(&config_path)?;
+    if problems.is_empty() {
+        println!("{config_path:?} has no problems");
+        return Ok(true);
+    }
+    for problem in &problems {
+        println!("{problem}");
+    }
+    Ok(false)
+}
+
+/// Print the config with all the defaults filled in.
+fn dump(args: Args) -> eyre::Result<bool> {
+    let config = load_config(args.config_path)?;
+    print!("{}", config.dump(args.show_secrets)?);
+    Ok(true)
+}
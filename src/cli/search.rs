@@ -0,0 +1,368 @@
+//! `metasearch search`, which searches without starting the server and prints
+//! the results.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+};
+
+use chrono::Utc;
+use eyre::bail;
+use scraper::{ElementRef, Html, Node};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::{
+    engines::{
+        self, operators::ParsedQuery, Engine, EngineProgressUpdate, EngineSelection,
+        ProgressUpdateData, ResponseForTab, SearchQuery, SearchResult, SearchTab,
+    },
+    web::search::{format_age, format_duration, strip_engines_prefix},
+};
+
+use super::{flag_value, load_config};
+
+#[derive(Debug, Default)]
+struct Args {
+    query: Vec<String>,
+    tab: SearchTab,
+    json: bool,
+    config_path: Option<PathBuf>,
+}
+
+impl Args {
+    fn parse(args: impl IntoIterator<Item = String>) -> eyre::Result<Self> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--tab" => {
+                    let tab = flag_value(&mut args, &arg)?;
+                    let Ok(tab) = SearchTab::from_str(&tab) else {
+                        bail!("unknown tab {tab:?}, expected all, images, news, or videos");
+                    };
+                    parsed.tab = tab;
+                }
+                "--format" => {
+                    parsed.json = match flag_value(&mut args, &arg)?.as_str() {
+                        "text" => false,
+                        "json" => true,
+                        format => bail!("unknown format {format:?}, expected text or json"),
+                    }
+                }
+                "--config" => parsed.config_path = Some(flag_value(&mut args, &arg)?.into()),
+                // everything after -- is the query, so it can start with --
+                "--" => parsed.query.extend(args.by_ref()),
+                _ if arg.starts_with("--") => bail!("unknown argument {arg:?}"),
+                // the query doesn't have to be quoted
+                _ => parsed.query.push(arg),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// Search and print the results. Returns whether the search succeeded, which
+/// it didn't if every engine failed or nothing was found.
+pub async fn run(args: impl IntoIterator<Item = String>) -> eyre::Result<bool> {
+    let args = Args::parse(args)?;
+    let config = Arc::new(load_config(args.config_path)?);
+
+    let (query, engines_prefix) = strip_engines_prefix(&args.query.join(" "));
+    if query.is_empty() {
+        bail!("no query provided");
+    }
+    if args.tab == SearchTab::Images && !config.image_search.enabled {
+        bail!("image search is disabled in the config");
+    }
+
+    let query = SearchQuery {
+        parsed: ParsedQuery::parse(&query),
+        query,
+        tab: args.tab,
+        page: 1,
+        time_range: None,
+        engine_selection: EngineSelection::parse(
+            engines_prefix.as_deref().unwrap_or_default(),
+            &config,
+        ),
+        request_headers: HashMap::new(),
+        ip: String::new(),
+        config,
+    };
+
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+    engines::search(&query, progress_tx).await?;
+
+    let mut response = None;
+    let mut postsearch_infobox = None;
+    let mut requested_engines = HashSet::new();
+    let mut failed_engines = HashSet::new();
+    while let Some(progress_update) = progress_rx.recv().await {
+        match progress_update.data {
+            ProgressUpdateData::Engine {
+                engine,
+                update: EngineProgressUpdate::Requesting,
+            } => {
+                requested_engines.insert(engine);
+            }
+            ProgressUpdateData::Engine {
+                engine,
+                update: EngineProgressUpdate::Error(e),
+            } => {
+                warn!("{engine} failed: {e}");
+                failed_engines.insert(engine);
+            }
+            ProgressUpdateData::Response(r) => response = Some(*r),
+            ProgressUpdateData::PostSearchInfobox(infobox) => postsearch_infobox = Some(infobox),
+            _ => {}
+        }
+    }
+    let Some(mut response) = response else {
+        bail!("the search didn't return a response");
+    };
+    // post-search infoboxes are only shown when there wasn't already one
+    if let ResponseForTab::All(response) = &mut response {
+        if response.infobox.is_none() {
+            response.infobox = postsearch_infobox;
+        }
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&response)?);
+    } else {
+        print!("{}", format_response(&response));
+    }
+
+    let every_engine_failed =
+        !requested_engines.is_empty() && requested_engines.is_subset(&failed_engines);
+    Ok(!every_engine_failed && has_results(&response))
+}
+
+/// Whether the response has anything in it, including an answer or infobox.
+fn has_results(response: &ResponseForTab) -> bool {
+    match response {
+        ResponseForTab::All(response) => {
+            !response.search_results.is_empty()
+                || response.answer.is_some()
+                || response.infobox.is_some()
+                || response.featured_snippet.is_some()
+        }
+        ResponseForTab::Images(response) => !response.image_results.is_empty(),
+        ResponseForTab::News(response) => !response.news_results.is_empty(),
+        ResponseForTab::Videos(response) => !response.video_results.is_empty(),
+    }
+}
+
+fn format_response(response: &ResponseForTab) -> String {
+    let now = Utc::now();
+    let mut sections = Vec::new();
+
+    match response {
+        ResponseForTab::All(response) => {
+            if let Some(answer) = &response.answer {
+                sections.push(format!(
+                    "Answer from {}:\n{}",
+                    answer.engine,
+                    html_to_text(&answer.html.0)
+                ));
+            }
+            if let Some(snippet) = &response.featured_snippet {
+                sections.push(format!(
+                    "Featured snippet from {}:\n{}\n{}\n{}",
+                    snippet.engine, snippet.title, snippet.url, snippet.description
+                ));
+            }
+            for (i, result) in response.search_results.iter().enumerate() {
+                sections.push(format_result(
+                    i,
+                    result,
+                    &[&result.result.url, &result.result.description],
+                    &result.result.title,
+                ));
+            }
+            if let Some(infobox) = &response.infobox {
+                sections.push(format!(
+                    "Infobox from {}:\n{}",
+                    infobox.engine,
+                    html_to_text(&infobox.html.0)
+                ));
+            }
+        }
+        ResponseForTab::Images(response) => {
+            for (i, result) in response.image_results.iter().enumerate() {
+                let image = &result.result;
+                let size = format!("{}x{}", image.width, image.height);
+                sections.push(format_result(
+                    i,
+                    result,
+                    &[&image.image_url, &image.page_url, &size],
+                    &image.title,
+                ));
+            }
+        }
+        ResponseForTab::News(response) => {
+            for (i, result) in response.news_results.iter().enumerate() {
+                let article = &result.result;
+                let info = [
+                    article.source.clone(),
+                    article
+                        .published
+                        .map(|published| format_age(published, now))
+                        .unwrap_or_default(),
+                ];
+                sections.push(format_result(
+                    i,
+                    result,
+                    &[&article.url, &join_info(&info), &article.description],
+                    &article.title,
+                ));
+            }
+        }
+        ResponseForTab::Videos(response) => {
+            for (i, result) in response.video_results.iter().enumerate() {
+                let video = &result.result;
+                let info = [
+                    video.channel.clone(),
+                    video.duration.map(format_duration).unwrap_or_default(),
+                    video
+                        .published
+                        .map(|published| format_age(published, now))
+                        .unwrap_or_default(),
+                ];
+                sections.push(format_result(
+                    i,
+                    result,
+                    &[&video.url, &join_info(&info), &video.description],
+                    &video.title,
+                ));
+            }
+        }
+    }
+
+    if sections.is_empty() {
+        return "No results.\n".to_string();
+    }
+    let mut text = sections.join("\n\n");
+    text.push('\n');
+    text
+}
+
+/// Format a numbered result with its title, the given lines, and the engines
+/// that it came from. Empty lines are left out.
+fn format_result<R: serde::Serialize>(
+    index: usize,
+    result: &SearchResult<R>,
+    lines: &[&str],
+    title: &str,
+) -> String {
+    let mut text = format!("{}. {title}", index + 1);
+    let engines = result
+        .engines
+        .iter()
+        .map(Engine::id)
+        .collect::<Vec<_>>()
+        .join(", ");
+    for line in lines.iter().copied().chain([engines.as_str()]) {
+        if !line.is_empty() {
+            text.push_str("\n   ");
+            text.push_str(line);
+        }
+    }
+    text
+}
+
+fn join_info(info: &[String]) -> String {
+    info.iter()
+        .filter(|part| !part.is_empty())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+/// Convert the HTML of an answer or infobox to text that can be shown in a
+/// terminal.
+fn html_to_text(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    let mut text = String::new();
+    push_element_text(fragment.root_element(), &mut text);
+
+    text.lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn push_element_text(element: ElementRef, text: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(t) => {
+                // whitespace is collapsed like it would be in a browser
+                for c in t.chars() {
+                    if !c.is_whitespace() {
+                        text.push(c);
+                    } else if !text.is_empty() && !text.ends_with([' ', '\n']) {
+                        text.push(' ');
+                    }
+                }
+            }
+            Node::Element(el) => {
+                let Some(child_element) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                match el.name() {
+                    "script" | "style" => {}
+                    "br" => text.push('\n'),
+                    "pre" => {
+                        text.push('\n');
+                        text.extend(child_element.text());
+                        text.push('\n');
+                    }
+                    "li" => {
+                        text.push_str("\n- ");
+                        push_element_text(child_element, text);
+                    }
+                    "p" | "div" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "ul" | "ol"
+                    | "table" | "tr" | "blockquote" | "details" | "summary" | "dl" | "dt"
+                    | "dd" | "section" | "hr" => {
+                        text.push('\n');
+                        push_element_text(child_element, text);
+                        text.push('\n');
+                    }
+                    _ => push_element_text(child_element, text),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args_after_double_dash_are_the_query() {
+        let args = Args::parse(
+            ["--tab", "news", "--", "--version", "flag", "--tab"].map(String::from),
+        )
+        .unwrap();
+        assert_eq!(args.tab, SearchTab::News);
+        assert_eq!(args.query, ["--version", "flag", "--tab"]);
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        assert_eq!(
+            html_to_text(
+                "<h2><a href=\"https://example.com\">Example</a></h2>\
+                 <p>Some   <b>bold</b>\n text.</p><ul><li>one</li><li>two</li></ul>\
+                 <script>alert(1)</script><pre>fn main() {\n    foo();\n}</pre>"
+            ),
+            "Example\nSome bold text.\n- one\n- two\nfn main() {\n    foo();\n}"
+        );
+    }
+}
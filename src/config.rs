@@ -1,21 +1,36 @@
 use std::{
     collections::HashMap,
-    fs,
+    fmt, fs,
     net::SocketAddr,
     path::Path,
+    str::FromStr,
     sync::{Arc, LazyLock},
 };
 
-use serde::Deserialize;
-use tracing::info;
+use eyre::bail;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
 
-use crate::engines::Engine;
+use crate::engines::{Engine, EngineOptions};
+
+mod dump;
+mod env;
+mod validate;
+pub use validate::ConfigProblem;
 
 impl Default for Config {
     fn default() -> Self {
         Config {
             bind: "0.0.0.0:28019".parse().unwrap(),
             api: false,
+            metrics: false,
+            status_token: None,
+            search_deadline_ms: 10_000,
+            proxy: None,
+            safe_search: SafeSearch::Off,
+            min_safe_search: SafeSearch::Off,
+            language: "en".to_string(),
+            region: "US".to_string(),
             ui: UiConfig {
                 show_engine_list_separator: false,
                 show_version_info: false,
@@ -33,6 +48,17 @@ impl Default for Config {
                 },
             },
             engines: Arc::new(EnginesConfig::default()),
+            circuit_breaker: CircuitBreakerConfig {
+                enabled: true,
+                failure_threshold: 5,
+                backoff_secs: 60,
+                max_backoff_secs: 60 * 30,
+            },
+            cache: CacheConfig {
+                enabled: true,
+                ttl_secs: 5 * 60,
+                max_entries: 1000,
+            },
             urls: UrlsConfig {
                 replace: vec![(
                     HostAndPath::new("minecraft.fandom.com/wiki/"),
@@ -40,14 +66,37 @@ impl Default for Config {
                 )],
                 weight: vec![],
             },
+            bangs: [
+                (
+                    "w",
+                    "https://en.wikipedia.org/wiki/Special:Search?search={query}",
+                ),
+                ("g", "https://www.google.com/search?q={query}"),
+                ("b", "https://www.bing.com/search?q={query}"),
+                ("ddg", "https://duckduckgo.com/?q={query}"),
+                (
+                    "gh",
+                    "https://github.com/search?q={query}&type=repositories",
+                ),
+                ("crates", "https://crates.io/search?q={query}"),
+                ("docs", "https://docs.rs/releases/search?query={query}"),
+                (
+                    "mdn",
+                    "https://developer.mozilla.org/en-US/search?q={query}",
+                ),
+                ("so", "https://stackoverflow.com/search?q={query}"),
+                ("yt", "https://www.youtube.com/results?search_query={query}"),
+                ("osm", "https://www.openstreetmap.org/search?query={query}"),
+            ]
+            .into_iter()
+            .map(|(bang, url)| (bang.to_string(), url.to_string()))
+            .collect(),
         }
     }
 }
 
 impl Default for EnginesConfig {
     fn default() -> Self {
-        use toml::value::Value;
-
         let mut map = HashMap::new();
         // engines are enabled by default, so engines that aren't listed here are
         // enabled
@@ -56,25 +105,7 @@ impl Default for EnginesConfig {
         map.insert(Engine::Google, EngineConfig::new().with_weight(1.05));
         map.insert(Engine::Bing, EngineConfig::new().with_weight(1.0));
         map.insert(Engine::Brave, EngineConfig::new().with_weight(1.25));
-        map.insert(
-            Engine::Marginalia,
-            EngineConfig::new().with_weight(0.15).with_extra(
-                vec![(
-                    "args".to_string(),
-                    Value::Table(
-                        vec![
-                            ("profile".to_string(), Value::String("corpo".to_string())),
-                            ("js".to_string(), Value::String("default".to_string())),
-                            ("adtech".to_string(), Value::String("default".to_string())),
-                        ]
-                        .into_iter()
-                        .collect(),
-                    ),
-                )]
-                .into_iter()
-                .collect(),
-            ),
-        );
+        map.insert(Engine::Marginalia, EngineConfig::new().with_weight(0.15));
 
         // additional search engines
         map.insert(
@@ -93,6 +124,11 @@ impl Default for EnginesConfig {
             Engine::Yep,
             EngineConfig::new().with_weight(0.10).disabled(),
         );
+        // public instances come and go, so operators have to choose one
+        map.insert(
+            Engine::Invidious,
+            EngineConfig::new().with_weight(0.5).disabled(),
+        );
 
         // calculators (give them a high weight so they're always the first thing in
         // autocomplete)
@@ -102,17 +138,12 @@ impl Default for EnginesConfig {
             EngineConfig::new().with_weight(10.0).disabled(),
         );
 
-        // other engines
-        map.insert(
-            Engine::Mdn,
-            EngineConfig::new().with_extra(
-                vec![("max_sections".to_string(), Value::Integer(1))]
-                    .into_iter()
-                    .collect(),
-            ),
-        );
-
-        Self { map }
+        let mut engines = Self {
+            map,
+            custom: HashMap::new(),
+        };
+        engines.parse_options();
+        engines
     }
 }
 
@@ -121,7 +152,10 @@ impl Default for EngineConfig {
         Self {
             enabled: true,
             weight: 1.0,
+            timeout_ms: None,
+            proxy: None,
             extra: Default::default(),
+            options: None,
         }
     }
 }
@@ -139,9 +173,6 @@ impl EngineConfig {
             ..self
         }
     }
-    pub fn with_extra(self, extra: toml::Table) -> Self {
-        Self { extra, ..self }
-    }
 }
 
 //
@@ -151,40 +182,175 @@ pub struct Config {
     pub bind: SocketAddr,
     /// Whether the JSON API should be accessible.
     pub api: bool,
+    /// Whether Prometheus metrics should be accessible at `/metrics`.
+    pub metrics: bool,
+    /// If this is set, the status page at `/status` can only be seen with
+    /// `?token=<token>` or an `Authorization: Bearer <token>` header.
+    pub status_token: Option<String>,
+    /// How long we wait for engines before showing the results we have so far.
+    /// Engines that haven't responded by then are reported as timed out.
+    pub search_deadline_ms: u64,
+    /// The proxy that requests to engines are sent through, unless the engine
+    /// has its own. For example `socks5h://127.0.0.1:9050`.
+    pub proxy: Option<String>,
+    /// How much explicit content should be filtered out of the results. Users
+    /// can override this in their settings.
+    pub safe_search: SafeSearch,
+    /// The lowest safe search level that users can choose in their settings.
+    pub min_safe_search: SafeSearch,
+    /// The language that results should be in, like `en`. Users can override
+    /// this in their settings or with the `lang` parameter.
+    pub language: String,
+    /// The country that results should be for, like `US`.
+    pub region: String,
     pub ui: UiConfig,
     pub image_search: ImageSearchConfig,
     // wrapped in an arc to make Config cheaper to clone
     pub engines: Arc<EnginesConfig>,
+    pub circuit_breaker: CircuitBreakerConfig,
+    pub cache: CacheConfig,
     pub urls: UrlsConfig,
+    /// Bangs like `!w`, without the `!`. `{query}` in the url is replaced with
+    /// the rest of the query.
+    pub bangs: HashMap<String, String>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct PartialConfig {
     pub bind: Option<SocketAddr>,
     pub api: Option<bool>,
+    pub metrics: Option<bool>,
+    pub status_token: Option<String>,
+    pub search_deadline_ms: Option<u64>,
+    pub proxy: Option<String>,
+    pub safe_search: Option<SafeSearch>,
+    pub min_safe_search: Option<SafeSearch>,
+    pub language: Option<String>,
+    pub region: Option<String>,
     pub ui: Option<PartialUiConfig>,
     pub image_search: Option<PartialImageSearchConfig>,
     pub engines: Option<PartialEnginesConfig>,
+    pub custom_engines: Option<HashMap<String, PartialCustomEngineConfig>>,
+    pub circuit_breaker: Option<PartialCircuitBreakerConfig>,
+    pub cache: Option<PartialCacheConfig>,
     pub urls: Option<PartialUrlsConfig>,
+    pub bangs: Option<HashMap<String, String>>,
 }
 
 impl Config {
     pub fn overlay(&mut self, partial: PartialConfig) {
         self.bind = partial.bind.unwrap_or(self.bind);
         self.api = partial.api.unwrap_or(self.api);
+        self.metrics = partial.metrics.unwrap_or(self.metrics);
+        self.status_token = partial.status_token.or(self.status_token.take());
+        self.search_deadline_ms = partial
+            .search_deadline_ms
+            .unwrap_or(self.search_deadline_ms);
+        self.proxy = partial.proxy.or(self.proxy.take());
+        self.min_safe_search = partial.min_safe_search.unwrap_or(self.min_safe_search);
+        self.safe_search = partial
+            .safe_search
+            .unwrap_or(self.safe_search)
+            .max(self.min_safe_search);
+        if let Some(language) = partial.language {
+            if !self.set_locale(&language) {
+                error!("Invalid language {language:?}, ignoring it");
+            }
+        }
+        if let Some(region) = partial.region {
+            if !self.set_locale(&format!("{}-{region}", self.language)) {
+                error!("Invalid region {region:?}, ignoring it");
+            }
+        }
         self.ui.overlay(partial.ui.unwrap_or_default());
         self.image_search
             .overlay(partial.image_search.unwrap_or_default());
-        if let Some(partial_engines) = partial.engines {
+        if partial.engines.is_some() || partial.custom_engines.is_some() {
             let mut engines = self.engines.as_ref().clone();
-            engines.overlay(partial_engines);
+            engines.overlay(partial.engines.unwrap_or_default());
+            engines.overlay_custom(partial.custom_engines.unwrap_or_default());
+            engines.parse_options();
             self.engines = Arc::new(engines);
         }
+        self.circuit_breaker
+            .overlay(partial.circuit_breaker.unwrap_or_default());
+        self.cache.overlay(partial.cache.unwrap_or_default());
         self.urls.overlay(partial.urls.unwrap_or_default());
+        for (bang, url) in partial.bangs.unwrap_or_default() {
+            let bang = bang.trim_start_matches('!').to_lowercase();
+            // an empty url removes a built-in bang
+            if url.is_empty() {
+                self.bangs.remove(&bang);
+            } else {
+                self.bangs.insert(bang, url);
+            }
+        }
     }
 }
 
-#[derive(Debug, Clone)]
+impl Config {
+    /// Set the language, and optionally the region, from a tag like `de` or
+    /// `de-AT`. Returns false and doesn't change anything if the tag is
+    /// invalid.
+    pub fn set_locale(&mut self, tag: &str) -> bool {
+        let is_valid_part = |part: &str| {
+            (2..=3).contains(&part.len()) && part.chars().all(|c| c.is_ascii_alphanumeric())
+        };
+
+        let (language, region) = match tag.split_once(['-', '_']) {
+            Some((language, region)) => (language, Some(region)),
+            None => (tag, None),
+        };
+        if !is_valid_part(language) || !region.is_none_or(is_valid_part) {
+            return false;
+        }
+
+        self.language = language.to_lowercase();
+        if let Some(region) = region {
+            self.region = region.to_uppercase();
+        }
+        true
+    }
+
+    /// The value of the `Accept-Language` header that should be sent to
+    /// engines.
+    #[must_use]
+    pub fn accept_language(&self) -> String {
+        if self.language == "en" {
+            format!("en-{},en;q=0.5", self.region)
+        } else {
+            format!(
+                "{language}-{region},{language};q=0.9,en;q=0.5",
+                language = self.language,
+                region = self.region
+            )
+        }
+    }
+}
+
+/// These are ordered from the least to the most strict.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum SafeSearch {
+    Off,
+    Moderate,
+    Strict,
+}
+
+impl SafeSearch {
+    pub const ALL: [SafeSearch; 3] = [SafeSearch::Off, SafeSearch::Moderate, SafeSearch::Strict];
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SafeSearch::Off => "off",
+            SafeSearch::Moderate => "moderate",
+            SafeSearch::Strict => "strict",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
 pub struct UiConfig {
     pub show_engine_list_separator: bool,
     pub show_version_info: bool,
@@ -225,7 +391,7 @@ impl UiConfig {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Debug, Clone)]
 pub struct ImageSearchConfig {
     pub enabled: bool,
     pub show_engines: bool,
@@ -247,7 +413,7 @@ impl ImageSearchConfig {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Debug, Clone)]
 pub struct ImageProxyConfig {
     /// Whether we should proxy remote images through our server. This is mostly
     /// a privacy feature.
@@ -269,15 +435,75 @@ impl ImageProxyConfig {
     }
 }
 
+#[derive(Serialize, Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Whether engines that keep failing should be temporarily suspended.
+    pub enabled: bool,
+    /// How many times in a row an engine has to fail before it's suspended.
+    pub failure_threshold: u32,
+    /// How long an engine is suspended for the first time, in seconds. This
+    /// doubles every time the engine is still failing after its suspension.
+    pub backoff_secs: u64,
+    /// The longest an engine can be suspended for, in seconds.
+    pub max_backoff_secs: u64,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct PartialCircuitBreakerConfig {
+    pub enabled: Option<bool>,
+    pub failure_threshold: Option<u32>,
+    pub backoff_secs: Option<u64>,
+    pub max_backoff_secs: Option<u64>,
+}
+
+impl CircuitBreakerConfig {
+    pub fn overlay(&mut self, partial: PartialCircuitBreakerConfig) {
+        self.enabled = partial.enabled.unwrap_or(self.enabled);
+        self.failure_threshold = partial.failure_threshold.unwrap_or(self.failure_threshold);
+        self.backoff_secs = partial.backoff_secs.unwrap_or(self.backoff_secs);
+        self.max_backoff_secs = partial.max_backoff_secs.unwrap_or(self.max_backoff_secs);
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CacheConfig {
+    /// Whether search results should be cached in memory.
+    pub enabled: bool,
+    /// How long search results are kept in the cache, in seconds.
+    pub ttl_secs: u64,
+    /// The maximum number of searches that are kept in the cache. The oldest
+    /// ones are removed when it's full.
+    pub max_entries: usize,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct PartialCacheConfig {
+    pub enabled: Option<bool>,
+    pub ttl_secs: Option<u64>,
+    pub max_entries: Option<usize>,
+}
+
+impl CacheConfig {
+    pub fn overlay(&mut self, partial: PartialCacheConfig) {
+        self.enabled = partial.enabled.unwrap_or(self.enabled);
+        self.ttl_secs = partial.ttl_secs.unwrap_or(self.ttl_secs);
+        self.max_entries = partial.max_entries.unwrap_or(self.max_entries);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct EnginesConfig {
     pub map: HashMap<Engine, EngineConfig>,
+    /// The engines that were defined in the `custom_engines` section. Their
+    /// weight and whether they're enabled are still stored in `map`.
+    pub custom: HashMap<Engine, CustomEngineConfig>,
 }
 
 #[derive(Deserialize, Debug, Default)]
 pub struct PartialEnginesConfig {
+    /// The keys are engine ids. Ids that aren't built-in engines are ignored.
     #[serde(flatten)]
-    pub map: HashMap<Engine, PartialDefaultableEngineConfig>,
+    pub map: HashMap<String, PartialDefaultableEngineConfig>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -289,7 +515,11 @@ pub enum PartialDefaultableEngineConfig {
 
 impl EnginesConfig {
     pub fn overlay(&mut self, partial: PartialEnginesConfig) {
-        for (key, value) in partial.map {
+        for (id, value) in partial.map {
+            let Ok(key) = Engine::from_str(&id) else {
+                error!("Unknown engine {id}, ignoring it");
+                continue;
+            };
             let full = match value {
                 PartialDefaultableEngineConfig::Boolean(enabled) => PartialEngineConfig {
                     enabled: Some(enabled),
@@ -307,8 +537,77 @@ impl EnginesConfig {
         }
     }
 
-    pub fn get(&self, engine: Engine) -> &EngineConfig {
-        self.map.get(&engine).unwrap_or(&DEFAULT_ENGINE_CONFIG_REF)
+    pub fn overlay_custom(&mut self, partial: HashMap<String, PartialCustomEngineConfig>) {
+        for (name, custom) in partial {
+            if Engine::from_str(&name).is_ok() {
+                error!("Custom engine {name} has the same name as a built-in engine, ignoring it");
+                continue;
+            }
+
+            let method = match custom.validate() {
+                Ok(method) => method,
+                Err(err) => {
+                    error!("Custom engine {name} {err}, ignoring it");
+                    continue;
+                }
+            };
+
+            let engine = Engine::Custom(name.into());
+            self.map
+                .entry(engine.clone())
+                .or_default()
+                .overlay(PartialEngineConfig {
+                    enabled: custom.enabled,
+                    weight: custom.weight,
+                    timeout_ms: custom.timeout_ms,
+                    proxy: custom.proxy,
+                    extra: Default::default(),
+                });
+            self.custom.insert(
+                engine,
+                CustomEngineConfig {
+                    engine_type: custom.engine_type,
+                    url: custom.url,
+                    method,
+                    headers: custom.headers.into_iter().collect(),
+                    body: custom.body,
+                    result: custom.result,
+                    title: custom.title,
+                    href: custom.href,
+                    description: custom.description,
+                    featured_snippet: custom.featured_snippet,
+                    featured_snippet_title: custom.featured_snippet_title,
+                    featured_snippet_href: custom.featured_snippet_href,
+                    featured_snippet_description: custom.featured_snippet_description,
+                },
+            );
+        }
+    }
+
+    /// Parse the options of every engine that has them, so engines don't have
+    /// to parse them for every request. Invalid options are replaced with the
+    /// defaults.
+    fn parse_options(&mut self) {
+        for engine in Engine::all() {
+            let engine_config = self.map.entry(engine.clone()).or_default();
+            let Some(result) = engine.parse_options(&engine_config.extra, |_| {}) else {
+                continue;
+            };
+            engine_config.options = match result {
+                Ok(options) => Some(options),
+                Err(err) => {
+                    error!(
+                        "Invalid options for {engine}, using the defaults: {}",
+                        err.to_string().trim().replace('\n', " ")
+                    );
+                    engine.default_options()
+                }
+            };
+        }
+    }
+
+    pub fn get(&self, engine: &Engine) -> &EngineConfig {
+        self.map.get(engine).unwrap_or(&DEFAULT_ENGINE_CONFIG_REF)
     }
 }
 
@@ -317,14 +616,26 @@ pub struct EngineConfig {
     pub enabled: bool,
     /// The priority of this engine relative to the other engines.
     pub weight: f64,
-    /// Per-engine configs. These are parsed at request time.
+    /// How long a request to this engine can take before it's cancelled. If
+    /// this isn't set then the client's default timeout is used.
+    pub timeout_ms: Option<u64>,
+    /// The proxy that requests to this engine are sent through. This overrides
+    /// the global proxy, and setting it to an empty string disables the proxy
+    /// for this engine.
+    pub proxy: Option<String>,
+    /// The keys in the engine's section that aren't shared by every engine.
+    /// These are parsed into `options`.
     pub extra: toml::Table,
+    /// The engine's own options, if it has any.
+    pub options: Option<EngineOptions>,
 }
 
 #[derive(Deserialize, Clone, Debug, Default)]
 pub struct PartialEngineConfig {
     pub enabled: Option<bool>,
     pub weight: Option<f64>,
+    pub timeout_ms: Option<u64>,
+    pub proxy: Option<String>,
     #[serde(flatten)]
     pub extra: toml::Table,
 }
@@ -333,14 +644,146 @@ impl EngineConfig {
     pub fn overlay(&mut self, partial: PartialEngineConfig) {
         self.enabled = partial.enabled.unwrap_or(self.enabled);
         self.weight = partial.weight.unwrap_or(self.weight);
+        self.timeout_ms = partial.timeout_ms.or(self.timeout_ms);
+        self.proxy = partial.proxy.or(self.proxy.take());
         self.extra.extend(partial.extra);
     }
 }
 
+/// A search engine that's defined in the config. These either scrape a page of
+/// HTML results with CSS selectors, or get results from a JSON API with JSON
+/// pointers.
+#[derive(Debug, Clone)]
+pub struct CustomEngineConfig {
+    pub engine_type: CustomEngineType,
+    /// The url that gets requested. `{query}` is replaced with the url-encoded
+    /// query, and `{page}` is replaced with the page number. If the url doesn't
+    /// have `{page}` then the engine is only requested for the first page.
+    pub url: String,
+    pub method: reqwest::Method,
+    pub headers: Vec<(String, String)>,
+    /// The body of the request. `{query}` is replaced with the query, escaped
+    /// so it can be put in a JSON string, and `{page}` is replaced with the
+    /// page number.
+    pub body: Option<String>,
+    /// For HTML engines these are CSS selectors. For JSON engines, `result` is
+    /// a JSON pointer to the array of results, and the others are JSON pointers
+    /// that are relative to each result.
+    pub result: String,
+    pub title: String,
+    pub href: String,
+    pub description: String,
+    pub featured_snippet: String,
+    pub featured_snippet_title: String,
+    pub featured_snippet_href: String,
+    pub featured_snippet_description: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CustomEngineType {
+    #[default]
+    Html,
+    Json,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PartialCustomEngineConfig {
+    pub enabled: Option<bool>,
+    pub weight: Option<f64>,
+    pub timeout_ms: Option<u64>,
+    pub proxy: Option<String>,
+    #[serde(rename = "type", default)]
+    pub engine_type: CustomEngineType,
+    pub url: String,
+    pub method: Option<String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub body: Option<String>,
+    #[serde(default)]
+    pub result: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub href: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub featured_snippet: String,
+    #[serde(default)]
+    pub featured_snippet_title: String,
+    #[serde(default)]
+    pub featured_snippet_href: String,
+    #[serde(default)]
+    pub featured_snippet_description: String,
+}
+
+impl PartialCustomEngineConfig {
+    /// Check that the engine's selectors or JSON pointers make sense, and parse
+    /// its method. The error is the reason the engine is invalid.
+    fn validate(&self) -> Result<reqwest::Method, String> {
+        let method = match &self.method {
+            Some(method) => reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+                .map_err(|_| format!("has an invalid method {method:?}"))?,
+            None => reqwest::Method::GET,
+        };
+
+        match self.engine_type {
+            CustomEngineType::Html => {
+                let required_selectors = [
+                    ("result", &self.result),
+                    ("title", &self.title),
+                    ("href", &self.href),
+                    ("description", &self.description),
+                ];
+                if let Some((key, _)) = required_selectors.iter().find(|(_, s)| s.is_empty()) {
+                    return Err(format!("is missing {key}"));
+                }
+                let invalid_selector = required_selectors
+                    .into_iter()
+                    .chain([
+                        ("featured_snippet", &self.featured_snippet),
+                        ("featured_snippet_title", &self.featured_snippet_title),
+                        ("featured_snippet_href", &self.featured_snippet_href),
+                        (
+                            "featured_snippet_description",
+                            &self.featured_snippet_description,
+                        ),
+                    ])
+                    .find(|(_, s)| !s.is_empty() && scraper::Selector::parse(s).is_err());
+                if let Some((key, selector)) = invalid_selector {
+                    return Err(format!("has an invalid {key} selector {selector:?}"));
+                }
+            }
+            CustomEngineType::Json => {
+                let pointers = [
+                    ("title", &self.title),
+                    ("href", &self.href),
+                    ("description", &self.description),
+                ];
+                if let Some((key, _)) = pointers[..2].iter().find(|(_, s)| s.is_empty()) {
+                    return Err(format!("is missing {key}"));
+                }
+                // an empty pointer is allowed for the results since it refers to the whole
+                // response
+                let invalid_pointer = [("result", &self.result)]
+                    .into_iter()
+                    .chain(pointers)
+                    .find(|(_, s)| !s.is_empty() && !s.starts_with('/'));
+                if let Some((key, pointer)) = invalid_pointer {
+                    return Err(format!(
+                        "has an invalid {key} JSON pointer {pointer:?} (it should start with /)"
+                    ));
+                }
+            }
+        }
+
+        Ok(method)
+    }
+}
+
 impl Config {
     pub fn read_or_create(config_path: &Path) -> eyre::Result<Self> {
-        let mut config = Config::default();
-
         if !config_path.exists() {
             info!("No config found, creating one at {config_path:?}");
             let default_config_str = include_str!("../config-default.toml");
@@ -350,10 +793,42 @@ impl Config {
             fs::write(config_path, default_config_str)?;
         }
 
-        let given_config = toml::from_str::<PartialConfig>(&fs::read_to_string(config_path)?)?;
-        config.overlay(given_config);
+        Self::read(config_path)
+    }
+
+    /// Read the config from the file and overlay it on the defaults, followed by
+    /// the options that are set with environment variables. Unlike
+    /// [`Config::read_or_create`], this doesn't create the file if it's
+    /// missing. Unknown keys are logged as warnings and ignored, but configs
+    /// with other problems like invalid values are rejected.
+    pub fn read(config_path: &Path) -> eyre::Result<Self> {
+        let (config, problems) = Self::check(config_path)?;
+        let (unknown_keys, problems) = problems
+            .into_iter()
+            .partition::<Vec<_>, _>(|problem| problem.unknown_key);
+        for problem in &unknown_keys {
+            warn!("{config_path:?} {problem}");
+        }
+        if !problems.is_empty() {
+            let problems = problems
+                .iter()
+                .map(|problem| format!("  {problem}"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            bail!("{config_path:?} has problems:\n{problems}");
+        }
         Ok(config)
     }
+
+    /// Read the config from the file and the environment variables, and return
+    /// it along with every problem that was found in it. The parts of the config
+    /// that have problems are ignored.
+    pub fn check(config_path: &Path) -> eyre::Result<(Self, Vec<ConfigProblem>)> {
+        validate::check(
+            &fs::read_to_string(config_path)?,
+            &env::EnvOverride::from_env()?,
+        )
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -370,6 +845,15 @@ impl HostAndPath {
         }
     }
 }
+impl fmt::Display for HostAndPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.host)
+        } else {
+            write!(f, "{}/{}", self.host, self.path)
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct UrlsConfig {
@@ -385,13 +869,16 @@ pub struct PartialUrlsConfig {
 }
 impl UrlsConfig {
     pub fn overlay(&mut self, partial: PartialUrlsConfig) {
-        for (from, to) in partial.replace {
-            let from = HostAndPath::new(&from);
+        for (from_str, to) in partial.replace {
+            let from = HostAndPath::new(&from_str);
             if to.is_empty() {
                 // setting the value to an empty string removes it
-                let index = self.replace.iter().position(|(u, _)| u == &from);
+                let Some(index) = self.replace.iter().position(|(u, _)| u == &from) else {
+                    error!("There's no url replacement for {from_str:?} to remove, ignoring it");
+                    continue;
+                };
                 // swap_remove is fine because the order of this vec doesn't matter
-                self.replace.swap_remove(index.unwrap());
+                self.replace.swap_remove(index);
             } else {
                 let to = HostAndPath::new(&to);
                 self.replace.push((from, to));
@@ -0,0 +1,189 @@
+//! Overriding options in the config with environment variables, so an option
+//! can be changed without editing the config file.
+
+use std::{env, fs};
+
+use eyre::{bail, eyre};
+
+/// Environment variables that start with this override the config. The rest
+/// of the name is the keys that lead to the option, separated by `__`.
+const PREFIX: &str = "METASEARCH__";
+/// Environment variables that end with this are the path to a file that has
+/// the value, which is useful for secrets.
+const FILE_SUFFIX: &str = "_FILE";
+
+/// An option that's set by an environment variable, like
+/// `METASEARCH__ENGINES__GOOGLE__WEIGHT=0.5`.
+#[derive(Debug, Clone)]
+pub struct EnvOverride {
+    /// The name of the environment variable.
+    pub var: String,
+    /// The keys that lead to the option, like `["engines", "google", "weight"]`.
+    pub path: Vec<String>,
+    pub value: toml::Value,
+    /// The value before it was parsed as TOML.
+    pub raw: String,
+}
+
+impl EnvOverride {
+    /// Get every override from the environment, sorted by the name of the
+    /// variable.
+    pub fn from_env() -> eyre::Result<Vec<Self>> {
+        Self::parse_vars(
+            env::vars_os().filter_map(|(var, value)| {
+                Some((var.into_string().ok()?, value.into_string().ok()?))
+            }),
+        )
+    }
+
+    pub(super) fn parse_vars(vars: impl IntoIterator<Item = (String, String)>) -> eyre::Result<Vec<Self>> {
+        let mut overrides = Vec::new();
+        for (var, value) in vars {
+            let Some(keys) = var.strip_prefix(PREFIX) else {
+                continue;
+            };
+
+            let (keys, raw, value) = match keys.strip_suffix(FILE_SUFFIX) {
+                Some(keys) => {
+                    let contents = fs::read_to_string(&value)
+                        .map_err(|err| eyre!("couldn't read {value:?} from {var}: {err}"))?;
+                    // files usually end with a newline that isn't part of the value
+                    let contents = contents.trim_end_matches(['\n', '\r']).to_string();
+                    (keys, contents.clone(), toml::Value::String(contents))
+                }
+                None => {
+                    let parsed = parse_value(&value);
+                    (keys, value, parsed)
+                }
+            };
+
+            let mut path = Vec::new();
+            for key in keys.split("__") {
+                if key.is_empty() {
+                    bail!("{var} isn't a valid config option");
+                }
+                // names like custom engines can have uppercase letters, but options are
+                // always lowercase
+                let key = if has_named_keys(&path) {
+                    key.to_string()
+                } else {
+                    key.to_lowercase()
+                };
+                path.push(key);
+            }
+            overrides.push(Self {
+                var,
+                path,
+                value,
+                raw,
+            });
+        }
+        overrides.sort_by(|a, b| a.var.cmp(&b.var));
+        Ok(overrides)
+    }
+
+    /// The same override with the value as a string, if it was parsed as
+    /// something else. This is used for options that are strings but look like
+    /// numbers or booleans, like a token that's only digits.
+    #[must_use]
+    pub fn as_string(&self) -> Option<Self> {
+        if self.value.is_str() {
+            return None;
+        }
+        Some(Self {
+            value: toml::Value::String(self.raw.clone()),
+            ..self.clone()
+        })
+    }
+
+    /// Set the option in the config. The error is the reason it couldn't be
+    /// set.
+    pub fn apply(&self, config: &mut toml::Table) -> Result<(), String> {
+        let (last_key, parent_keys) = self.path.split_last().expect("path isn't empty");
+        let mut table = config;
+        for (i, key) in parent_keys.iter().enumerate() {
+            let value = table
+                .entry(key.clone())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            let toml::Value::Table(inner_table) = value else {
+                return Err(format!("`{}` isn't a table", self.path[..=i].join(".")));
+            };
+            table = inner_table;
+        }
+        table.insert(last_key.clone(), self.value.clone());
+        Ok(())
+    }
+}
+
+/// Whether the keys of the table at the path are names that were chosen in
+/// the config, rather than options.
+fn has_named_keys(path: &[String]) -> bool {
+    matches!(
+        path.iter().map(String::as_str).collect::<Vec<_>>()[..],
+        ["custom_engines"]
+            | ["custom_engines", _, "headers"]
+            | ["bangs"]
+            | ["urls", "replace" | "weight"]
+    )
+}
+
+/// Values are parsed as TOML so numbers, booleans, and arrays work. Anything
+/// that isn't a valid TOML value is used as a string.
+fn parse_value(value: &str) -> toml::Value {
+    format!("value = {value}")
+        .parse::<toml::Table>()
+        .ok()
+        .filter(|table| table.len() == 1)
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vars() {
+        let secret_path = env::temp_dir().join("metasearch-test-env-secret");
+        fs::write(&secret_path, "hunter2\n").unwrap();
+
+        let overrides = EnvOverride::parse_vars([
+            (
+                "METASEARCH__ENGINES__GOOGLE__WEIGHT".to_string(),
+                "0.5".to_string(),
+            ),
+            ("METASEARCH__BIND".to_string(), "127.0.0.1:8080".to_string()),
+            (
+                "METASEARCH_RECORD_FIXTURES".to_string(),
+                "fixtures".to_string(),
+            ),
+            (
+                "METASEARCH__PROXY_FILE".to_string(),
+                secret_path.to_string_lossy().into_owned(),
+            ),
+            (
+                "METASEARCH__CUSTOM_ENGINES__MyEngine__HEADERS__X-Api-Key".to_string(),
+                "123".to_string(),
+            ),
+        ])
+        .unwrap();
+        fs::remove_file(&secret_path).unwrap();
+
+        let overrides = overrides
+            .iter()
+            .map(|o| (o.path.join("."), o.value.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            overrides,
+            [
+                ("bind".to_string(), "127.0.0.1:8080".into()),
+                (
+                    "custom_engines.MyEngine.headers.X-Api-Key".to_string(),
+                    123.into()
+                ),
+                ("engines.google.weight".to_string(), 0.5.into()),
+                ("proxy".to_string(), "hunter2".into()),
+            ]
+        );
+    }
+}
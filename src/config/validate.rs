@@ -0,0 +1,515 @@
+//! Checking the config for mistakes like misspelled keys, so they're reported
+//! when the config is loaded instead of being silently ignored.
+
+use std::{fmt, str::FromStr};
+
+use eyre::eyre;
+use serde::Deserialize;
+
+use crate::engines::Engine;
+
+use super::{env::EnvOverride, Config, HostAndPath, PartialConfig, PartialDefaultableEngineConfig};
+
+/// Something that's wrong with the config.
+#[derive(Debug, Clone)]
+pub struct ConfigProblem {
+    /// The line in the config file that the problem is on, starting at 1. This
+    /// is missing if the problem isn't caused by a specific key.
+    pub line: Option<usize>,
+    pub message: String,
+    /// Whether the problem is a key that isn't used, like a misspelled option
+    /// or engine. These are only warnings when the server loads the config,
+    /// since the rest of it still works.
+    pub unknown_key: bool,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {line}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Problems that haven't been given a line number yet. The path is the keys
+/// that lead to the problem, like `["engines", "google", "weight"]`, and the
+/// bool is whether it's an unknown key.
+#[derive(Default)]
+struct Problems(Vec<(Vec<String>, String, bool)>);
+
+impl Problems {
+    fn add(&mut self, path: &[&str], message: impl Into<String>) {
+        self.0.push((
+            path.iter().map(|key| key.to_string()).collect(),
+            message.into(),
+            false,
+        ));
+    }
+
+    fn unknown_key(&mut self, path: Vec<String>) {
+        let message = format!("unknown key `{}`", path.join("."));
+        self.0.push((path, message, true));
+    }
+}
+
+/// Parse the config, overlay the environment variable overrides on it, and
+/// check it for problems. The config is still returned if there are problems,
+/// with the invalid parts ignored. An error is only returned if the file isn't
+/// valid TOML or has values of the wrong type.
+pub fn check(
+    config_str: &str,
+    overrides: &[EnvOverride],
+) -> eyre::Result<(Config, Vec<ConfigProblem>)> {
+    let mut problems = Problems::default();
+
+    let mut on_unknown_key = |path: serde_ignored::Path| {
+        let mut keys = Vec::new();
+        push_path_keys(&path, &mut keys);
+        problems.unknown_key(keys);
+    };
+    let partial: PartialConfig = if overrides.is_empty() {
+        serde_ignored::deserialize(toml::Deserializer::new(config_str), on_unknown_key)?
+    } else {
+        let mut table = toml::from_str::<toml::Table>(config_str)?;
+        // values that look like numbers or booleans are used as strings if the
+        // option is a string
+        let typed_overrides = overrides
+            .iter()
+            .map(|env_override| match env_override.as_string() {
+                Some(string_override)
+                    if !deserializes_with(&table, env_override)
+                        && deserializes_with(&table, &string_override) =>
+                {
+                    string_override
+                }
+                _ => env_override.clone(),
+            })
+            .collect::<Vec<_>>();
+        let mut override_problems = Vec::new();
+        for env_override in &typed_overrides {
+            if let Err(err) = env_override.apply(&mut table) {
+                override_problems.push((env_override.path.clone(), err, false));
+            }
+        }
+        let partial = serde_ignored::deserialize(toml::Value::Table(table), &mut on_unknown_key)
+            .map_err(|err| {
+                let vars = overrides
+                    .iter()
+                    .map(|env_override| env_override.var.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                // toml's errors are on multiple lines
+                eyre!(
+                    "{} (with the options from {vars})",
+                    err.to_string().trim().replace('\n', " ")
+                )
+            })?;
+        problems.0.extend(override_problems);
+        partial
+    };
+    check_partial(&partial, &mut problems);
+
+    let mut config = Config::default();
+    config.overlay(partial);
+    check_values(&config, &mut problems);
+
+    let document = toml_edit::ImDocument::parse(config_str).ok();
+    let mut problems = problems
+        .0
+        .into_iter()
+        .map(|(path, message, unknown_key)| {
+            // problems with options that were set by environment variables aren't in the file
+            let in_file = document
+                .as_ref()
+                .is_some_and(|document| has_key(document.as_table(), &path));
+            if let Some(env_override) = overrides.iter().rev().find(|env_override| {
+                path.starts_with(&env_override.path)
+                    || (!in_file && env_override.path.starts_with(&path))
+            }) {
+                return ConfigProblem {
+                    line: None,
+                    message: format!("{message} (set by {})", env_override.var),
+                    unknown_key,
+                };
+            }
+            ConfigProblem {
+                line: document.as_ref().and_then(|document| {
+                    let span = key_span(document.as_table(), &path)?;
+                    Some(config_str[..span.start].matches('\n').count() + 1)
+                }),
+                message,
+                unknown_key,
+            }
+        })
+        .collect::<Vec<_>>();
+    problems.sort_by_key(|problem| problem.line.unwrap_or(usize::MAX));
+    Ok((config, problems))
+}
+
+/// Whether the config and the engines' options can be deserialized with the
+/// override applied.
+fn deserializes_with(table: &toml::Table, env_override: &EnvOverride) -> bool {
+    let mut table = table.clone();
+    if env_override.apply(&mut table).is_err() {
+        return false;
+    }
+    let Ok(partial) = PartialConfig::deserialize(toml::Value::Table(table)) else {
+        return false;
+    };
+    partial
+        .engines
+        .iter()
+        .flat_map(|engines| &engines.map)
+        .all(|(id, engine_config)| {
+            let PartialDefaultableEngineConfig::Full(engine_config) = engine_config else {
+                return true;
+            };
+            Engine::from_str(id)
+                .ok()
+                .and_then(|engine| engine.parse_options(&engine_config.extra, |_| {}))
+                .is_none_or(|result| result.is_ok())
+        })
+}
+
+fn push_path_keys(path: &serde_ignored::Path, keys: &mut Vec<String>) {
+    use serde_ignored::Path;
+
+    match path {
+        Path::Root => {}
+        Path::Seq { parent, index } => {
+            push_path_keys(parent, keys);
+            keys.push(index.to_string());
+        }
+        Path::Map { parent, key } => {
+            push_path_keys(parent, keys);
+            keys.push(key.clone());
+        }
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => push_path_keys(parent, keys),
+    }
+}
+
+/// Find where the key at the end of the path is in the file. If only part of
+/// the path exists, this returns the last key that does.
+fn key_span(table: &dyn toml_edit::TableLike, path: &[String]) -> Option<std::ops::Range<usize>> {
+    let (first, rest) = path.split_first()?;
+    let (key, item) = table.get_key_value(first)?;
+    match item.as_table_like() {
+        Some(inner_table) => key_span(inner_table, rest).or_else(|| key.span()),
+        None => key.span(),
+    }
+}
+
+fn has_key(table: &dyn toml_edit::TableLike, path: &[String]) -> bool {
+    let Some((first, rest)) = path.split_first() else {
+        return true;
+    };
+    table.get(first).is_some_and(|item| {
+        rest.is_empty()
+            || item
+                .as_table_like()
+                .is_some_and(|inner_table| has_key(inner_table, rest))
+    })
+}
+
+/// Check the things that can only be checked before the config is overlaid on
+/// the defaults, since invalid values are ignored by the overlay.
+fn check_partial(partial: &PartialConfig, problems: &mut Problems) {
+    if let Some(language) = &partial.language {
+        if !Config::default().set_locale(language) {
+            problems.add(&["language"], format!("invalid language {language:?}"));
+        }
+    }
+    if let Some(region) = &partial.region {
+        if !Config::default().set_locale(&format!("en-{region}")) {
+            problems.add(&["region"], format!("invalid region {region:?}"));
+        }
+    }
+    if let (Some(safe_search), Some(min_safe_search)) =
+        (partial.safe_search, partial.min_safe_search)
+    {
+        if safe_search < min_safe_search {
+            problems.add(
+                &["safe_search"],
+                format!(
+                    "safe_search is {:?} but min_safe_search is {:?}",
+                    safe_search.as_str(),
+                    min_safe_search.as_str()
+                ),
+            );
+        }
+    }
+
+    if let Some(engines) = &partial.engines {
+        for (id, engine_config) in &engines.map {
+            if Engine::from_str(id).is_err() {
+                problems.0.push((
+                    vec!["engines".to_string(), id.clone()],
+                    format!("unknown engine `{id}`"),
+                    true,
+                ));
+            }
+            if let PartialDefaultableEngineConfig::Full(engine_config) = engine_config {
+                if engine_config
+                    .weight
+                    .is_some_and(|weight| !weight.is_finite() || weight < 0.)
+                {
+                    problems.add(
+                        &["engines", id, "weight"],
+                        "engine weights can't be negative",
+                    );
+                }
+            }
+        }
+    }
+
+    for (id, custom) in partial.custom_engines.iter().flatten() {
+        if Engine::from_str(id).is_ok() {
+            problems.add(
+                &["custom_engines", id],
+                format!("custom engine `{id}` has the same name as a built-in engine"),
+            );
+        }
+        if let Err(err) = custom.validate() {
+            problems.add(
+                &["custom_engines", id],
+                format!("custom engine `{id}` {err}"),
+            );
+        }
+        if custom
+            .weight
+            .is_some_and(|weight| !weight.is_finite() || weight < 0.)
+        {
+            problems.add(
+                &["custom_engines", id, "weight"],
+                "engine weights can't be negative",
+            );
+        }
+    }
+
+    if let Some(urls) = &partial.urls {
+        let default_replacements = Config::default().urls.replace;
+        for (from, to) in &urls.replace {
+            let from_host_and_path = HostAndPath::new(from);
+            if to.is_empty()
+                && !default_replacements
+                    .iter()
+                    .any(|(default_from, _)| default_from == &from_host_and_path)
+            {
+                problems.add(
+                    &["urls", "replace", from],
+                    format!("there's no url replacement for {from:?} to remove"),
+                );
+            }
+        }
+    }
+}
+
+/// Check the values in the final config.
+fn check_values(config: &Config, problems: &mut Problems) {
+    if config.search_deadline_ms == 0 {
+        problems.add(&["search_deadline_ms"], "search_deadline_ms can't be 0");
+    }
+    check_proxy(config.proxy.as_deref(), &["proxy"], problems);
+
+    if config.image_search.proxy.enabled && config.image_search.proxy.max_download_size == 0 {
+        problems.add(
+            &["image_search", "proxy", "max_download_size"],
+            "max_download_size can't be 0",
+        );
+    }
+
+    let circuit_breaker = &config.circuit_breaker;
+    if circuit_breaker.enabled {
+        if circuit_breaker.failure_threshold == 0 {
+            problems.add(
+                &["circuit_breaker", "failure_threshold"],
+                "failure_threshold can't be 0",
+            );
+        }
+        if circuit_breaker.backoff_secs > circuit_breaker.max_backoff_secs {
+            problems.add(
+                &["circuit_breaker", "backoff_secs"],
+                "backoff_secs can't be more than max_backoff_secs",
+            );
+        }
+    }
+
+    if config.cache.enabled && config.cache.max_entries == 0 {
+        problems.add(
+            &["cache", "max_entries"],
+            "max_entries can't be 0 while the cache is enabled",
+        );
+    }
+
+    for engine in Engine::all_with_custom(config) {
+        let engine_config = config.engines.get(&engine);
+        let section = match engine {
+            Engine::Custom(_) => "custom_engines",
+            _ => "engines",
+        };
+        let id = engine.id();
+
+        if engine_config.timeout_ms == Some(0) {
+            problems.add(&[section, id, "timeout_ms"], "timeout_ms can't be 0");
+        }
+        check_proxy(
+            engine_config.proxy.as_deref(),
+            &[section, id, "proxy"],
+            problems,
+        );
+
+        check_options(&engine, section, &engine_config.extra, problems);
+    }
+}
+
+fn check_proxy(proxy: Option<&str>, path: &[&str], problems: &mut Problems) {
+    // an empty proxy means no proxy
+    if let Some(proxy) = proxy.filter(|proxy| !proxy.is_empty()) {
+        if let Err(err) = reqwest::Proxy::all(proxy) {
+            problems.add(path, format!("invalid proxy {proxy:?}: {err}"));
+        }
+    }
+}
+
+/// Check the engine's options by parsing them the same way they're parsed when
+/// the config is loaded.
+fn check_options(engine: &Engine, section: &str, extra: &toml::Table, problems: &mut Problems) {
+    let path = [section, engine.id()];
+    let mut unknown_keys = Vec::new();
+    let result = engine.parse_options(extra, |key_path| {
+        let mut keys = path.iter().map(|key| key.to_string()).collect();
+        push_path_keys(&key_path, &mut keys);
+        unknown_keys.push(keys);
+    });
+    let Some(result) = result else {
+        // the engine doesn't have any options of its own
+        for key in extra.keys() {
+            problems.unknown_key(vec![
+                section.to_string(),
+                engine.id().to_string(),
+                key.clone(),
+            ]);
+        }
+        return;
+    };
+
+    for keys in unknown_keys {
+        problems.unknown_key(keys);
+    }
+    if let Err(err) = result {
+        problems.add(
+            &path,
+            // toml's errors are on multiple lines
+            format!(
+                "invalid options for {engine}: {}",
+                err.to_string().trim().replace('\n', " ")
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(config_str: &str) -> Vec<String> {
+        let (_, problems) = check(config_str, &[]).unwrap();
+        problems.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_default_config_has_no_problems() {
+        assert!(problems(include_str!("../../config-default.toml")).is_empty());
+    }
+
+    #[test]
+    fn test_problems_have_line_numbers() {
+        assert_eq!(
+            problems(
+                "api = true\n\
+                 ui.sitename = \"x\"\n\
+                 [engines]\n\
+                 gogle = false\n\
+                 [engines.google]\n\
+                 wieght = 2.0\n\
+                 [engines.mdn]\n\
+                 max_sections = \"1\"\n"
+            ),
+            [
+                "line 2: unknown key `ui.sitename`",
+                "line 4: unknown engine `gogle`",
+                "line 6: unknown key `engines.google.wieght`",
+                "line 7: invalid options for mdn: invalid type: string \"1\", expected usize in `max_sections`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_keys_are_marked() {
+        let (_, problems) = check(
+            "[engines]\n\
+             gogle = false\n\
+             [engines.google]\n\
+             wieght = 2.0\n\
+             weight = -1.0\n",
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            problems
+                .iter()
+                .map(|problem| problem.unknown_key)
+                .collect::<Vec<_>>(),
+            [true, true, false]
+        );
+    }
+
+    #[test]
+    fn test_env_overrides_that_look_like_numbers() {
+        let overrides = EnvOverride::parse_vars([
+            ("METASEARCH__STATUS_TOKEN".to_string(), "123456".to_string()),
+            (
+                "METASEARCH__ENGINES__GOOGLE__WEIGHT".to_string(),
+                "2".to_string(),
+            ),
+        ])
+        .unwrap();
+        let (config, problems) = check("", &overrides).unwrap();
+        assert!(problems.is_empty());
+        assert_eq!(config.status_token.as_deref(), Some("123456"));
+        assert_eq!(config.engines.get(&Engine::Google).weight, 2.);
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let env_override = |var: &str, path: &[&str], value: toml::Value| EnvOverride {
+            var: var.to_string(),
+            path: path.iter().map(|key| key.to_string()).collect(),
+            raw: match &value {
+                toml::Value::String(s) => s.clone(),
+                value => value.to_string(),
+            },
+            value,
+        };
+        let (config, problems) = check(
+            "[engines.google]\nweight = 2.0\n",
+            &[
+                env_override(
+                    "METASEARCH__ENGINES__GOOGLE__WEIGHT",
+                    &["engines", "google", "weight"],
+                    0.5.into(),
+                ),
+                env_override("METASEARCH__UI__SITE", &["ui", "site"], "x".into()),
+            ],
+        )
+        .unwrap();
+        assert_eq!(config.engines.get(&Engine::Google).weight, 0.5);
+        assert_eq!(
+            problems.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["unknown key `ui.site` (set by METASEARCH__UI__SITE)"]
+        );
+    }
+}
@@ -52,7 +52,7 @@ pub struct WiktionaryDefinition {
 }
 
 pub fn parse_response(
-    HttpResponse { res, body, .. }: &HttpResponse,
+    HttpResponse { res, body, config }: &HttpResponse,
 ) -> eyre::Result<EngineResponse> {
     let url = res.url();
 
@@ -68,7 +68,9 @@ pub fn parse_response(
 
     let word = key_to_title(mediawiki_key);
 
-    let Some(entries) = res.0.get("en") else {
+    // wiktionary's definition api only exists in english, but it has entries for
+    // words in other languages too
+    let Some(entries) = res.0.get(&config.language).or_else(|| res.0.get("en")) else {
         return Ok(EngineResponse::new());
     };
 
@@ -3,7 +3,7 @@ use std::{cell::Cell, sync::LazyLock};
 use fend_core::SpanKind;
 use maud::{html, PreEscaped};
 
-use crate::engines::EngineResponse;
+use crate::{config::Config, engines::EngineResponse};
 
 use super::regex;
 
@@ -20,7 +20,7 @@ pub fn request(query: &str) -> EngineResponse {
     })
 }
 
-pub fn request_autocomplete(query: &str) -> Vec<String> {
+pub fn request_autocomplete(query: &str, _config: &Config) -> Vec<String> {
     let mut results = Vec::new();
 
     let query = clean_query(query);
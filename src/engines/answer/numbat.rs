@@ -10,7 +10,7 @@ use numbat::{
 };
 use tracing::debug;
 
-use crate::engines::EngineResponse;
+use crate::{config::Config, engines::EngineResponse};
 
 pub fn request(query: &str) -> EngineResponse {
     let query = clean_query(query);
@@ -29,7 +29,7 @@ pub fn request(query: &str) -> EngineResponse {
     })
 }
 
-pub fn request_autocomplete(query: &str) -> Vec<String> {
+pub fn request_autocomplete(query: &str, _config: &Config) -> Vec<String> {
     let mut results = Vec::new();
 
     let query = clean_query(query);
@@ -79,7 +79,7 @@ fn is_potential_request(query: &str) -> bool {
     true
 }
 
-fn interpret(query: &str) -> Option<(Statement, Markup)> {
+fn interpret(query: &str) -> Option<(Statement<'_>, Markup)> {
     if !is_potential_request(query) {
         return None;
     }
@@ -9,8 +9,8 @@ use super::regex;
 pub fn request(query: &str) -> EngineResponse {
     match evaluate(query) {
         None => EngineResponse::new(),
-        Some(TimeResponse::Current { time, timezone }) => EngineResponse::answer_html(html! {
-            p.answer-query { "Current time in " (timezone_to_string(timezone)) }
+        Some(TimeResponse::Current { time }) => EngineResponse::answer_html(html! {
+            p.answer-query { "Current time in " (timezone_to_string(time)) }
             h3 {
                 b { (time.format("%-I:%M %P")) }
                 span.answer-comment {
@@ -19,8 +19,6 @@ pub fn request(query: &str) -> EngineResponse {
             }
         }),
         Some(TimeResponse::Conversion {
-            source_timezone,
-            target_timezone,
             source_time,
             target_time,
             source_offset,
@@ -37,15 +35,15 @@ pub fn request(query: &str) -> EngineResponse {
                 p.answer-query {
                     (source_time.format("%-I:%M %P"))
                     " "
-                    (timezone_to_string(source_timezone))
+                    (timezone_to_string(source_time))
                     " to "
-                    (timezone_to_string(target_timezone))
+                    (timezone_to_string(target_time))
                 }
                 h3 {
                     b { (target_time.format("%-I:%M %P")) }
                     " "
                     span.answer-comment {
-                        (timezone_to_string(target_timezone)) " (" (delta) ")"
+                        (timezone_to_string(target_time)) " (" (delta) ")"
                     }
                 }
             })
@@ -57,11 +55,8 @@ pub fn request(query: &str) -> EngineResponse {
 enum TimeResponse {
     Current {
         time: DateTime<Tz>,
-        timezone: Tz,
     },
     Conversion {
-        source_timezone: Tz,
-        target_timezone: Tz,
         source_time: DateTime<Tz>,
         target_time: DateTime<Tz>,
         source_offset: chrono::Duration,
@@ -101,21 +96,24 @@ fn evaluate(query: &str) -> Option<TimeResponse> {
                 minute,
                 0,
             )?;
+            // the dst offset has to be included, otherwise daylight savings timezones are
+            // off by an hour for half the year
+            let source_offset = source_offset.base_utc_offset() + source_offset.dst_offset();
+            let target_offset = target_offset.base_utc_offset() + target_offset.dst_offset();
+
             let source_time_utc = chrono::Utc
                 .from_local_datetime(&source_time_naive)
                 .latest()?
-                - source_offset.base_utc_offset();
+                - source_offset;
 
             let source_time = source_time_utc.with_timezone(&source_timezone);
             let target_time = source_time_utc.with_timezone(&target_timezone);
 
             return Some(TimeResponse::Conversion {
-                source_timezone,
-                target_timezone,
                 source_time,
                 target_time,
-                source_offset: source_offset.base_utc_offset(),
-                target_offset: target_offset.base_utc_offset(),
+                source_offset,
+                target_offset,
             });
         }
     }
@@ -131,7 +129,7 @@ fn evaluate(query: &str) -> Option<TimeResponse> {
     {
         if let Some(timezone) = parse_timezone(timezone_name.as_str()) {
             let time = chrono::Utc::now().with_timezone(&timezone);
-            return Some(TimeResponse::Current { time, timezone });
+            return Some(TimeResponse::Current { time });
         }
     }
 
@@ -141,16 +139,20 @@ fn evaluate(query: &str) -> Option<TimeResponse> {
 fn parse_timezone(timezone_name: &str) -> Option<Tz> {
     match timezone_name.to_lowercase().as_str() {
         "cst" | "cdt" => Some(Tz::CST6CDT),
+        // est is always utc-5, so only these follow daylight savings
+        "edt" | "et" => Some(Tz::EST5EDT),
         _ => Tz::from_str_insensitive(timezone_name)
             .ok()
             .or_else(|| Tz::from_str_insensitive(&format!("etc/{timezone_name}")).ok()),
     }
 }
 
-fn timezone_to_string(tz: Tz) -> String {
-    match tz {
-        Tz::CST6CDT => "CST".to_string(),
-        _ => {
+/// The name of the time's timezone. Zones with daylight savings that are
+/// named after their abbreviations show the one that's in use, like EDT.
+fn timezone_to_string(time: DateTime<Tz>) -> String {
+    match time.timezone() {
+        Tz::CST6CDT | Tz::EST5EDT => time.format("%Z").to_string(),
+        tz => {
             let tz_string = tz.name();
             if let Some(tz_string) = tz_string.strip_prefix("Etc/") {
                 tz_string.to_string()
@@ -167,7 +169,7 @@ mod tests {
 
     #[test]
     fn test_evaluate() {
-        let response = evaluate("9 pm est to CST").unwrap();
+        let response = evaluate("9 pm et to CST").unwrap();
         let TimeResponse::Conversion {
             source_time,
             target_time,
@@ -177,10 +179,20 @@ mod tests {
             panic!("Expected TimeResponse::Conversion, got {response:?}");
         };
 
-        // we don't check the exact offsets since it depends on daylight savings, cst
-        // will always be 1 hour behind est though
+        // we don't check the exact offsets since it depends on daylight savings, ct
+        // will always be 1 hour behind et though
 
         assert_eq!(source_time.format("%-I:%M %P").to_string(), "9:00 pm");
         assert_eq!(target_time.format("%-I:%M %P").to_string(), "8:00 pm");
     }
+
+    #[test]
+    fn test_est_has_no_daylight_savings() {
+        let response = evaluate("9 pm est to utc").unwrap();
+        let TimeResponse::Conversion { target_time, .. } = response else {
+            panic!("Expected TimeResponse::Conversion, got {response:?}");
+        };
+        assert_eq!(target_time.format("%-I:%M %P").to_string(), "2:00 am");
+        assert_eq!(timezone_to_string(target_time), "UTC");
+    }
 }
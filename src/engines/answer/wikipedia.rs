@@ -4,11 +4,40 @@ use maud::html;
 use serde::Deserialize;
 use url::Url;
 
-use crate::engines::{EngineResponse, RequestResponse, CLIENT};
+use crate::engines::{EngineResponse, HttpResponse, RequestResponse, SearchQuery, CLIENT};
 
 use super::colorpicker;
 
-pub fn request(mut query: &str) -> RequestResponse {
+/// The languages that have their own Wikipedia, as the subdomains of
+/// wikipedia.org. The language can be chosen by the user, so it's only put in
+/// the url if it's one of these.
+const LANGUAGES: &[&str] = &[
+    "ab", "ace", "ady", "af", "ak", "als", "alt", "am", "ami", "an", "ang", "anp", "ar", "arc",
+    "ary", "arz", "as", "ast", "atj", "av", "avk", "awa", "ay", "az", "azb", "ba", "ban", "bar",
+    "bcl", "be", "bg", "bh", "bi", "bjn", "blk", "bm", "bn", "bo", "bpy", "br", "bs", "bug", "bxr",
+    "ca", "cdo", "ce", "ceb", "ch", "chr", "chy", "ckb", "co", "cr", "crh", "cs", "csb", "cu",
+    "cv", "cy", "da", "dag", "de", "din", "diq", "dsb", "dty", "dv", "dz", "ee", "el", "eml", "en",
+    "eo", "es", "et", "eu", "ext", "fa", "fat", "ff", "fi", "fj", "fo", "fon", "fr", "frp", "frr",
+    "fur", "fy", "ga", "gag", "gan", "gcr", "gd", "gl", "glk", "gn", "gom", "gor", "got", "gpe",
+    "gu", "guc", "gur", "guw", "gv", "ha", "hak", "haw", "he", "hi", "hif", "hr", "hsb", "ht",
+    "hu", "hy", "hyw", "ia", "id", "ie", "ig", "ik", "ilo", "inh", "io", "is", "it", "iu", "ja",
+    "jam", "jbo", "jv", "ka", "kaa", "kab", "kbd", "kbp", "kcg", "kg", "ki", "kk", "kl", "km",
+    "kn", "ko", "koi", "krc", "ks", "ksh", "ku", "kv", "kw", "ky", "la", "lad", "lb", "lbe", "lez",
+    "lfn", "lg", "li", "lij", "lld", "lmo", "ln", "lo", "lrc", "lt", "ltg", "lv", "mad", "mai",
+    "mdf", "mg", "mhr", "mi", "min", "mk", "ml", "mn", "mni", "mnw", "mr", "mrj", "ms", "mt",
+    "mwl", "my", "myv", "mzn", "na", "nah", "nap", "nds", "ne", "new", "nia", "nl", "nn", "no",
+    "nov", "nqo", "nrm", "nso", "nv", "ny", "oc", "olo", "om", "or", "os", "pa", "pag", "pam",
+    "pap", "pcd", "pcm", "pdc", "pfl", "pi", "pih", "pl", "pms", "pnb", "pnt", "ps", "pt", "pwn",
+    "qu", "rm", "rmy", "rn", "ro", "rue", "rup", "rw", "sa", "sah", "sat", "sc", "scn", "sco",
+    "sd", "se", "sg", "sh", "shi", "shn", "si", "sk", "skr", "sl", "sm", "smn", "sn", "so", "sq",
+    "sr", "srn", "ss", "st", "stq", "su", "sv", "sw", "szl", "szy", "ta", "tay", "tcy", "te",
+    "tet", "tg", "th", "ti", "tk", "tl", "tly", "tn", "to", "tpi", "tr", "trv", "ts", "tt", "tum",
+    "tw", "ty", "tyv", "udm", "ug", "uk", "ur", "uz", "ve", "vec", "vep", "vi", "vls", "vo", "wa",
+    "war", "wo", "wuu", "xal", "xh", "xmf", "yi", "yo", "za", "zea", "zgh", "zh", "zu",
+];
+
+pub fn request(search: &SearchQuery) -> RequestResponse {
+    let mut query = search.query.as_str();
     if !colorpicker::MatchedColorModel::new(query).is_empty() {
         // "color picker" is a wikipedia article but we only want to show the
         // actual color picker answer
@@ -23,10 +52,14 @@ pub fn request(mut query: &str) -> RequestResponse {
         query = stripped_query
     }
 
+    let language = Some(search.config.language.as_str())
+        .filter(|language| LANGUAGES.binary_search(language).is_ok())
+        .unwrap_or("en");
+
     CLIENT
         .get(
             Url::parse_with_params(
-                "https://en.wikipedia.org/w/api.php",
+                &format!("https://{language}.wikipedia.org/w/api.php"),
                 &[
                     ("format", "json"),
                     ("action", "query"),
@@ -70,7 +103,17 @@ pub struct WikipediaThumbnail {
     pub height: u64,
 }
 
-pub fn parse_response(body: &str) -> eyre::Result<EngineResponse> {
+pub fn parse_response(
+    HttpResponse { res, body, .. }: &HttpResponse,
+) -> eyre::Result<EngineResponse> {
+    // the page is on the same wikipedia that we requested, which depends on the
+    // language
+    let host = res
+        .url()
+        .host_str()
+        .unwrap_or("en.wikipedia.org")
+        .to_string();
+
     let Ok(res) = serde_json::from_str::<WikipediaResponse>(body) else {
         return Ok(EngineResponse::new());
     };
@@ -106,7 +149,7 @@ pub fn parse_response(body: &str) -> eyre::Result<EngineResponse> {
     }
 
     let page_title = title.replace(' ', "_");
-    let page_url = format!("https://en.wikipedia.org/wiki/{page_title}");
+    let page_url = format!("https://{host}/wiki/{page_title}");
 
     Ok(EngineResponse::infobox_html(html! {
         a href=(page_url) {
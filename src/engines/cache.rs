@@ -0,0 +1,161 @@
+//! An in-memory cache of merged search results, so repeated searches don't
+//! have to hit every engine again.
+
+use std::{
+    collections::HashMap,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+use crate::config::Config;
+
+use super::{
+    health, metrics, Engine, EngineSelection, Infobox, ResponseForTab, SearchQuery, SearchTab,
+    TimeRange,
+};
+
+static CACHE: LazyLock<Mutex<HashMap<CacheKey, CacheEntry>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    query: String,
+    tab: SearchTab,
+    page: usize,
+    time_range: Option<TimeRange>,
+    engine_selection: EngineSelection,
+    /// A fingerprint of the parts of the config that affect the results, so
+    /// users with different engine settings don't share cache entries.
+    config: String,
+}
+
+impl CacheKey {
+    fn new(query: &SearchQuery) -> Self {
+        Self {
+            query: query.query.clone(),
+            tab: query.tab,
+            page: query.page,
+            time_range: query.time_range,
+            engine_selection: query.engine_selection.clone(),
+            config: config_fingerprint(&query.config),
+        }
+    }
+}
+
+fn config_fingerprint(config: &Config) -> String {
+    let mut fingerprint = String::new();
+    // Engine::all_with_custom() has a stable order, unlike the hashmap in
+    // EnginesConfig
+    for engine in Engine::all_with_custom(config) {
+        fingerprint.push_str(&format!("{engine}={:?};", config.engines.get(&engine)));
+        if let Some(custom) = config.engines.custom.get(&engine) {
+            fingerprint.push_str(&format!("{custom:?};"));
+        }
+    }
+    fingerprint.push_str(&format!(
+        "{:?};{:?};{}",
+        config.urls,
+        config.safe_search,
+        config.accept_language()
+    ));
+    fingerprint
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedSearch {
+    pub response: ResponseForTab,
+    pub infobox: Option<Infobox>,
+}
+
+struct CacheEntry {
+    search: CachedSearch,
+    inserted_at: Instant,
+}
+
+/// Get the cached results for the query, if they exist and haven't expired.
+pub fn get(query: &SearchQuery) -> Option<CachedSearch> {
+    let cache_config = &query.config.cache;
+    if !cache_config.enabled {
+        return None;
+    }
+    if query.tab == SearchTab::Images && !query.config.image_search.enabled {
+        return None;
+    }
+
+    let key = CacheKey::new(query);
+    let mut cache = CACHE.lock();
+    let Some(entry) = cache.get(&key) else {
+        metrics::record_cache_lookup(false);
+        return None;
+    };
+    if entry.inserted_at.elapsed() > Duration::from_secs(cache_config.ttl_secs) {
+        cache.remove(&key);
+        metrics::record_cache_lookup(false);
+        return None;
+    }
+    metrics::record_cache_lookup(true);
+
+    let mut search = entry.search.clone();
+    // the cached response has the config of whoever made the search first, but it
+    // should be rendered with the current user's settings
+    let engine_health = health::statuses(&query.config);
+    match &mut search.response {
+        ResponseForTab::All(response) => {
+            response.config = query.config.clone();
+            response.engine_health = engine_health;
+        }
+        ResponseForTab::Images(response) => {
+            response.config = query.config.clone();
+            response.engine_health = engine_health;
+        }
+        ResponseForTab::News(response) => {
+            response.config = query.config.clone();
+            response.engine_health = engine_health;
+        }
+        ResponseForTab::Videos(response) => {
+            response.config = query.config.clone();
+            response.engine_health = engine_health;
+        }
+    }
+    Some(search)
+}
+
+pub fn insert(query: &SearchQuery, search: CachedSearch) {
+    let cache_config = &query.config.cache;
+    if !cache_config.enabled || cache_config.max_entries == 0 {
+        return;
+    }
+
+    if let ResponseForTab::All(response) = &search.response {
+        if let Some(answer) = &response.answer {
+            if !answer.engine.answer_is_cacheable() {
+                return;
+            }
+        }
+    }
+
+    let ttl = Duration::from_secs(cache_config.ttl_secs);
+    let mut cache = CACHE.lock();
+    cache.retain(|_, entry| entry.inserted_at.elapsed() <= ttl);
+    while cache.len() >= cache_config.max_entries {
+        // evict the oldest entry to make room
+        let Some(oldest_key) = cache
+            .iter()
+            .min_by_key(|(_, entry)| entry.inserted_at)
+            .map(|(key, _)| key.clone())
+        else {
+            break;
+        };
+        cache.remove(&oldest_key);
+    }
+
+    cache.insert(
+        CacheKey::new(query),
+        CacheEntry {
+            search,
+            inserted_at: Instant::now(),
+        },
+    );
+}
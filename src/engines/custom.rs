@@ -0,0 +1,143 @@
+//! Engines that are defined in the `custom_engines` section of the config
+//! instead of in code.
+
+use eyre::eyre;
+use tracing::error;
+use url::Url;
+
+use crate::{
+    config::{CustomEngineConfig, CustomEngineType},
+    parse::{parse_html_response_with_opts, ParseOpts, QueryMethod},
+    urls::normalize_url,
+};
+
+use super::{
+    Engine, EngineResponse, EngineSearchResult, HttpResponse, RequestResponse, SearchQuery, CLIENT,
+};
+
+pub fn request(engine: &Engine, query: &SearchQuery) -> RequestResponse {
+    let Some(custom) = query.config.engines.custom.get(engine) else {
+        return RequestResponse::None;
+    };
+
+    // engines without a {page} in their url can't be paginated
+    if query.page > 1 && !custom.url.contains("{page}") {
+        return RequestResponse::None;
+    }
+
+    let engine_query = query.engine_query(engine);
+    let time_range = query.time_range.map(|t| t.to_string()).unwrap_or_default();
+    let url = custom
+        .url
+        .replace("{query}", &urlencoding::encode(&engine_query))
+        .replace("{page}", &query.page.to_string())
+        .replace("{safe_search}", query.config.safe_search.as_str())
+        .replace("{language}", &query.config.language)
+        .replace("{region}", &query.config.region)
+        .replace("{time_range}", &time_range);
+    let url = match Url::parse(&url) {
+        Ok(url) => url,
+        Err(err) => {
+            error!("Invalid url for custom engine {engine}: {err}");
+            return RequestResponse::None;
+        }
+    };
+
+    let mut request = CLIENT.request(custom.method.clone(), url);
+    for (key, value) in &custom.headers {
+        request = request.header(key, value);
+    }
+    if let Some(body) = &custom.body {
+        // the query is escaped like a json string since that's what most request
+        // bodies are
+        let escaped_query = serde_json::to_string(&engine_query).unwrap_or_default();
+        let body = body
+            .replace("{query}", &escaped_query[1..escaped_query.len() - 1])
+            .replace("{page}", &query.page.to_string())
+            .replace("{safe_search}", query.config.safe_search.as_str())
+            .replace("{language}", &query.config.language)
+            .replace("{region}", &query.config.region)
+            .replace("{time_range}", &time_range);
+        request = request.body(body);
+    }
+    request.into()
+}
+
+pub fn parse_response(engine: &Engine, res: &HttpResponse) -> eyre::Result<EngineResponse> {
+    let custom = res
+        .config
+        .engines
+        .custom
+        .get(engine)
+        .ok_or_else(|| eyre!("custom engine {engine} isn't in the config"))?;
+
+    match custom.engine_type {
+        CustomEngineType::Html => parse_html(custom, &res.body),
+        CustomEngineType::Json => parse_json(custom, &res.body),
+    }
+}
+
+fn parse_html(custom: &CustomEngineConfig, body: &str) -> eyre::Result<EngineResponse> {
+    parse_html_response_with_opts(
+        body,
+        ParseOpts::new()
+            .result(&custom.result)
+            .title(custom.title.as_str())
+            .href(custom.href.as_str())
+            .description(custom.description.as_str())
+            .featured_snippet(&custom.featured_snippet)
+            .featured_snippet_title(optional_selector(&custom.featured_snippet_title))
+            .featured_snippet_href(optional_selector(&custom.featured_snippet_href))
+            .featured_snippet_description(optional_selector(&custom.featured_snippet_description)),
+    )
+}
+
+fn parse_json(custom: &CustomEngineConfig, body: &str) -> eyre::Result<EngineResponse> {
+    let json = serde_json::from_str::<serde_json::Value>(body)?;
+    let results = json
+        .pointer(&custom.result)
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| eyre!("no array of results at {:?}", custom.result))?;
+
+    let search_results = results
+        .iter()
+        .filter_map(|result| {
+            let url = json_string(result, &custom.href)?;
+            let title = json_string(result, &custom.title)?;
+            let description = if custom.description.is_empty() {
+                String::new()
+            } else {
+                json_string(result, &custom.description).unwrap_or_default()
+            };
+            Some(EngineSearchResult {
+                url: normalize_url(&url),
+                title,
+                description,
+            })
+        })
+        .collect();
+
+    Ok(EngineResponse {
+        search_results,
+        ..EngineResponse::new()
+    })
+}
+
+/// Get the value at the JSON pointer as a string, also accepting numbers and
+/// booleans.
+fn json_string(value: &serde_json::Value, pointer: &str) -> Option<String> {
+    match value.pointer(pointer)? {
+        serde_json::Value::String(s) => Some(s.trim().to_string()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn optional_selector(selector: &str) -> QueryMethod<'_> {
+    if selector.is_empty() {
+        QueryMethod::None
+    } else {
+        QueryMethod::CssSelector(selector)
+    }
+}
@@ -0,0 +1,425 @@
+//! Recorded upstream responses, so the engine parsers can be tested without
+//! making requests.
+//!
+//! If the `METASEARCH_RECORD_FIXTURES` environment variable is set to a
+//! directory, every response that an engine gets is saved there as
+//! `<engine>/<kind>-<query>.json`. The fixtures in `fixtures/` are replayed
+//! through the engine's parser by `cargo test`, and the parsed results are
+//! compared against the `.snap` file next to each fixture.
+
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, LazyLock},
+};
+
+use eyre::{bail, eyre};
+use reqwest::{
+    header::{self, HeaderMap},
+    ResponseBuilderExt, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::warn;
+use url::Url;
+
+use crate::config::Config;
+
+use super::{
+    operators::ParsedQuery, send_engine_request, Engine, EngineSelection, HttpResponse,
+    RequestResponse, SearchQuery, SearchTab, CLIENT,
+};
+
+/// The directory that responses are being recorded to, if any.
+static RECORD_DIR: LazyLock<Option<PathBuf>> =
+    LazyLock::new(|| env::var_os("METASEARCH_RECORD_FIXTURES").map(PathBuf::from));
+
+/// Which parser a fixture is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FixtureKind {
+    All,
+    Images,
+    News,
+    Videos,
+    PostSearch,
+}
+
+impl From<SearchTab> for FixtureKind {
+    fn from(tab: SearchTab) -> Self {
+        match tab {
+            SearchTab::All => Self::All,
+            SearchTab::Images => Self::Images,
+            SearchTab::News => Self::News,
+            SearchTab::Videos => Self::Videos,
+        }
+    }
+}
+
+impl fmt::Display for FixtureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::All => write!(f, "all"),
+            Self::Images => write!(f, "images"),
+            Self::News => write!(f, "news"),
+            Self::Videos => write!(f, "videos"),
+            Self::PostSearch => write!(f, "postsearch"),
+        }
+    }
+}
+
+impl FromStr for FixtureKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(Self::All),
+            "images" => Ok(Self::Images),
+            "news" => Ok(Self::News),
+            "videos" => Ok(Self::Videos),
+            "postsearch" => Ok(Self::PostSearch),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fixture {
+    #[serde(serialize_with = "serialize_engine_id")]
+    pub engine: Engine,
+    pub kind: FixtureKind,
+    pub query: String,
+    pub page: usize,
+    /// The url that the response came from, after redirects.
+    pub url: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Fixture {
+    #[must_use]
+    pub fn new(
+        engine: Engine,
+        kind: FixtureKind,
+        query: &SearchQuery,
+        res: &reqwest::Response,
+        body: &str,
+    ) -> Self {
+        Self::from_parts(
+            engine,
+            kind,
+            query,
+            res.url(),
+            res.status(),
+            res.headers(),
+            body,
+        )
+    }
+
+    fn from_parts(
+        engine: Engine,
+        kind: FixtureKind,
+        query: &SearchQuery,
+        url: &Url,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &str,
+    ) -> Self {
+        Self {
+            engine,
+            kind,
+            query: query.query.clone(),
+            page: query.page,
+            url: url.to_string(),
+            status: status.as_u16(),
+            headers: headers
+                .iter()
+                // cookies are the only headers that might identify us
+                .filter(|(name, _)| *name != header::SET_COOKIE)
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect(),
+            body: body.to_string(),
+        }
+    }
+
+    /// The name of the file that the fixture is saved to, relative to the
+    /// fixtures directory.
+    #[must_use]
+    pub fn file_name(&self) -> PathBuf {
+        let mut query_slug = String::new();
+        for c in self.query.chars() {
+            if c.is_alphanumeric() {
+                query_slug.extend(c.to_lowercase());
+            } else if !query_slug.is_empty() && !query_slug.ends_with('-') {
+                query_slug.push('-');
+            }
+        }
+        let query_slug = query_slug.trim_end_matches('-');
+
+        let mut name = format!("{}-{query_slug}", self.kind);
+        if self.page > 1 {
+            name.push_str(&format!("-page{}", self.page));
+        }
+        Path::new(self.engine.id()).join(format!("{name}.json"))
+    }
+
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|e| eyre!("couldn't parse {}: {e}", path.display()))
+    }
+
+    /// Load every fixture in the directory, which has a subdirectory for each
+    /// engine. The fixtures are sorted by their path.
+    pub fn load_dir(dir: &Path) -> eyre::Result<Vec<(PathBuf, Self)>> {
+        let mut fixtures = Vec::new();
+        for engine_dir in fs::read_dir(dir)? {
+            let engine_dir = engine_dir?.path();
+            if !engine_dir.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(&engine_dir)? {
+                let path = entry?.path();
+                if path.extension().is_some_and(|ext| ext == "json") {
+                    let fixture = Self::load(&path)?;
+                    fixtures.push((path, fixture));
+                }
+            }
+        }
+        fixtures.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(fixtures)
+    }
+
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut contents = serde_json::to_string_pretty(self)?;
+        contents.push('\n');
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Turn the fixture back into the response that the engine got.
+    pub fn http_response(&self, config: Arc<Config>) -> eyre::Result<HttpResponse> {
+        let mut builder = axum::http::Response::builder()
+            .status(self.status)
+            .url(Url::parse(&self.url)?);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        // the parsers only read the body that we give them, so the response's own
+        // body can be left empty
+        let res = reqwest::Response::from(builder.body(String::new())?);
+
+        Ok(HttpResponse {
+            res,
+            body: self.body.clone(),
+            config,
+        })
+    }
+
+    /// Run the fixture through the engine's parser and return the results as
+    /// JSON, which is what the snapshots contain.
+    pub fn parse(&self, config: Arc<Config>) -> eyre::Result<serde_json::Value> {
+        let res = self.http_response(config)?;
+        let engine = &self.engine;
+
+        let value = match self.kind {
+            FixtureKind::All => {
+                let response = engine.parse_response(&res)?;
+                json!({
+                    "search_results": response.search_results,
+                    "featured_snippet": response.featured_snippet,
+                    "answer_html": response.answer_html.map(|html| html.0),
+                    "infobox_html": response.infobox_html.map(|html| html.0),
+                })
+            }
+            FixtureKind::Images => {
+                json!({ "image_results": engine.parse_images_response(&res)?.image_results })
+            }
+            FixtureKind::News => {
+                let mut value =
+                    json!({ "news_results": engine.parse_news_response(&res)?.news_results });
+                mask_published(&mut value["news_results"]);
+                value
+            }
+            FixtureKind::Videos => {
+                let mut value =
+                    json!({ "video_results": engine.parse_videos_response(&res)?.video_results });
+                mask_published(&mut value["video_results"]);
+                value
+            }
+            FixtureKind::PostSearch => {
+                json!({ "html": engine.postsearch_parse_response(&res).map(|html| html.0) })
+            }
+        };
+        Ok(value)
+    }
+
+    /// Request the same thing from the engine again and return the new
+    /// response as a fixture.
+    pub async fn rerecord(&self, config: Arc<Config>) -> eyre::Result<Self> {
+        let engine = &self.engine;
+        let query = SearchQuery {
+            parsed: ParsedQuery::parse(&self.query),
+            query: self.query.clone(),
+            tab: match self.kind {
+                FixtureKind::Images => SearchTab::Images,
+                FixtureKind::News => SearchTab::News,
+                FixtureKind::Videos => SearchTab::Videos,
+                FixtureKind::All | FixtureKind::PostSearch => SearchTab::All,
+            },
+            page: self.page,
+            time_range: None,
+            engine_selection: EngineSelection::default(),
+            request_headers: HashMap::new(),
+            ip: String::new(),
+            config: config.clone(),
+        };
+
+        let request_response = match self.kind {
+            FixtureKind::All => engine.request(&query),
+            FixtureKind::Images => engine.request_images(&query),
+            FixtureKind::News => engine.request_news(&query),
+            FixtureKind::Videos => engine.request_videos(&query),
+            // post-search requests depend on the results from the other engines, so
+            // we just request the same url again
+            FixtureKind::PostSearch => CLIENT.get(&self.url).into(),
+        };
+        let RequestResponse::Http(request) = request_response else {
+            bail!("{engine} didn't make a request for {:?}", self.query);
+        };
+
+        let res = send_engine_request(request, engine, &config).await?;
+        let url = res.url().clone();
+        let status = res.status();
+        let headers = res.headers().clone();
+        if !status.is_success() {
+            bail!("{engine} responded with {status}");
+        }
+        let body = String::from_utf8_lossy(&res.bytes().await?).to_string();
+
+        Ok(Self::from_parts(
+            engine.clone(),
+            self.kind,
+            &query,
+            &url,
+            status,
+            &headers,
+            &body,
+        ))
+    }
+}
+
+/// Engines serialize as their variant name by default, but they're
+/// deserialized from their id.
+fn serialize_engine_id<S>(engine: &Engine, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_str(engine.id())
+}
+
+/// Replace the published dates with a placeholder, since they're often
+/// relative (like "3 hours ago") and so depend on when the parser ran.
+fn mask_published(results: &mut serde_json::Value) {
+    let Some(results) = results.as_array_mut() else {
+        return;
+    };
+    for result in results {
+        if let Some(published) = result.get_mut("published") {
+            if !published.is_null() {
+                *published = json!("<published>");
+            }
+        }
+    }
+}
+
+/// Save the response as a fixture if we're recording them.
+pub fn record(
+    engine: &Engine,
+    kind: FixtureKind,
+    query: &SearchQuery,
+    res: &reqwest::Response,
+    body: &str,
+) {
+    let Some(dir) = &*RECORD_DIR else {
+        return;
+    };
+    // custom engines can't be replayed without the config they came from
+    if matches!(engine, Engine::Custom(_)) {
+        return;
+    }
+
+    let fixture = Fixture::new(engine.clone(), kind, query, res, body);
+    let path = dir.join(fixture.file_name());
+    if let Err(e) = fixture.save(&path) {
+        warn!("couldn't record fixture to {}: {e}", path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")
+    }
+
+    /// Replay every fixture and compare the parsed results to the snapshot.
+    /// Set `UPDATE_SNAPSHOTS=1` to write the snapshots instead.
+    #[test]
+    fn test_fixtures() {
+        let config = Arc::new(Config::default());
+        let update = env::var_os("UPDATE_SNAPSHOTS").is_some();
+
+        let fixtures = Fixture::load_dir(&fixtures_dir()).unwrap();
+        assert!(!fixtures.is_empty());
+
+        for (path, fixture) in fixtures {
+            let parsed = fixture.parse(config.clone()).unwrap();
+            let mut parsed = serde_json::to_string_pretty(&parsed).unwrap();
+            parsed.push('\n');
+
+            let snapshot_path = path.with_extension("snap");
+            if update {
+                fs::write(&snapshot_path, &parsed).unwrap();
+                continue;
+            }
+            // a missing snapshot would otherwise be written from whatever the
+            // parser currently returns, which would make the test pass silently
+            let Ok(snapshot) = fs::read_to_string(&snapshot_path) else {
+                panic!(
+                    "{} is missing, run the tests with UPDATE_SNAPSHOTS=1 to write it",
+                    snapshot_path.display()
+                );
+            };
+            assert_eq!(
+                parsed,
+                snapshot,
+                "parsed results for {} don't match the snapshot",
+                path.display()
+            );
+        }
+    }
+
+    /// Request every fixture again from its engine. Run this with
+    /// `cargo test rerecord_fixtures -- --ignored`, and then update the
+    /// snapshots.
+    #[tokio::test]
+    #[ignore = "makes requests to every engine"]
+    async fn rerecord_fixtures() {
+        let config = Arc::new(Config::default());
+        for (path, fixture) in Fixture::load_dir(&fixtures_dir()).unwrap() {
+            match fixture.rerecord(config.clone()).await {
+                Ok(new_fixture) => new_fixture.save(&path).unwrap(),
+                Err(e) => eprintln!("couldn't rerecord {}: {e}", path.display()),
+            }
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! Tracks how well each engine has been responding, and temporarily stops
+//! requesting engines that keep failing (for example because they're serving
+//! us captchas). Only web searches are tracked, so an engine's images, news,
+//! or videos failing doesn't suspend its web search.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::config::{CircuitBreakerConfig, Config};
+
+use super::Engine;
+
+static HEALTH: LazyLock<Mutex<HashMap<Engine, EngineHealth>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// How many of the latest requests to each engine are kept for its recent
+/// success rate and median latency.
+const RECENT_REQUESTS: usize = 50;
+
+#[derive(Debug, Default)]
+struct EngineHealth {
+    successes: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    total_latency: Duration,
+    /// The engine won't be requested until this time has passed. After that,
+    /// a single probe request is allowed through to check if it's working
+    /// again.
+    suspended_until: Option<Instant>,
+    backoff: Duration,
+    /// Whether a probe request is currently in progress.
+    probing: bool,
+    /// The latencies of the latest requests, with None for the ones that
+    /// failed.
+    recent: VecDeque<Option<Duration>>,
+    last_error: Option<EngineError>,
+}
+
+impl EngineHealth {
+    fn push_recent(&mut self, latency: Option<Duration>) {
+        if self.recent.len() >= RECENT_REQUESTS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(latency);
+    }
+
+    fn state(&self) -> CircuitState {
+        match self.suspended_until {
+            None => CircuitState::Closed,
+            Some(until) if Instant::now() < until => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// The engine is working normally.
+    Closed,
+    /// The engine failed too many times and is suspended.
+    Open,
+    /// The engine's suspension is over and we're checking whether it works
+    /// again.
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineHealthStatus {
+    pub state: CircuitState,
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    pub average_latency_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineError {
+    pub message: String,
+    pub time: DateTime<Utc>,
+}
+
+/// A more detailed version of [`EngineHealthStatus`] for the status page.
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineHealthReport {
+    pub enabled: bool,
+    pub state: CircuitState,
+    /// How many requests the success rate and median latency are from.
+    pub recent_requests: usize,
+    /// Between 0 and 1.
+    pub recent_success_rate: Option<f64>,
+    /// Of the recent requests that succeeded.
+    pub median_latency_ms: Option<u64>,
+    pub last_error: Option<EngineError>,
+}
+
+/// Returns whether we should send a request to the engine, based on whether
+/// it's been failing recently.
+pub fn should_request(engine: &Engine, config: &CircuitBreakerConfig) -> bool {
+    if !config.enabled {
+        return true;
+    }
+
+    let mut health = HEALTH.lock();
+    let health = health.entry(engine.clone()).or_default();
+    match health.state() {
+        CircuitState::Closed => true,
+        CircuitState::Open => false,
+        CircuitState::HalfOpen => {
+            // only let one probe through at a time
+            if health.probing {
+                false
+            } else {
+                info!("Probing {engine} to check if it works again");
+                health.probing = true;
+                true
+            }
+        }
+    }
+}
+
+pub fn record_success(engine: &Engine, latency: Duration) {
+    let mut health = HEALTH.lock();
+    let health = health.entry(engine.clone()).or_default();
+
+    if health.suspended_until.is_some() {
+        info!("{engine} is working again");
+    }
+
+    health.successes += 1;
+    health.total_latency += latency;
+    health.push_recent(Some(latency));
+    health.consecutive_failures = 0;
+    health.suspended_until = None;
+    health.backoff = Duration::ZERO;
+    health.probing = false;
+}
+
+pub fn record_failure(engine: &Engine, config: &CircuitBreakerConfig, error: &eyre::Report) {
+    let mut health = HEALTH.lock();
+    let health = health.entry(engine.clone()).or_default();
+
+    health.failures += 1;
+    health.push_recent(None);
+    health.last_error = Some(EngineError {
+        message: without_urls(&format!("{error:#}")),
+        time: Utc::now(),
+    });
+    health.consecutive_failures += 1;
+    let was_probing = health.probing;
+    health.probing = false;
+
+    if !config.enabled {
+        return;
+    }
+
+    if was_probing || health.consecutive_failures >= config.failure_threshold {
+        // double the backoff every time the engine fails again after being suspended
+        health.backoff = if health.backoff.is_zero() {
+            Duration::from_secs(config.backoff_secs)
+        } else {
+            (health.backoff * 2).min(Duration::from_secs(config.max_backoff_secs))
+        };
+        health.suspended_until = Some(Instant::now() + health.backoff);
+        warn!(
+            "{engine} failed {} times in a row, suspending it for {:?}",
+            health.consecutive_failures, health.backoff
+        );
+    }
+}
+
+/// Get the health of every enabled engine that we've sent requests to.
+pub fn statuses(config: &Config) -> BTreeMap<Engine, EngineHealthStatus> {
+    let health = HEALTH.lock();
+    health
+        .iter()
+        .filter(|(engine, _)| config.engines.get(engine).enabled)
+        .map(|(engine, health)| {
+            let average_latency_ms =
+                (health.total_latency.as_millis() as u64).checked_div(health.successes);
+            (
+                engine.clone(),
+                EngineHealthStatus {
+                    state: health.state(),
+                    successes: health.successes,
+                    failures: health.failures,
+                    consecutive_failures: health.consecutive_failures,
+                    average_latency_ms,
+                },
+            )
+        })
+        .collect()
+}
+
+/// Get the detailed health of every engine, including the disabled ones.
+pub fn report(config: &Config) -> Vec<(Engine, EngineHealthReport)> {
+    let health = HEALTH.lock();
+    Engine::all_with_custom(config)
+        .into_iter()
+        .map(|engine| {
+            let enabled = config.engines.get(&engine).enabled;
+            let Some(health) = health.get(&engine) else {
+                return (
+                    engine,
+                    EngineHealthReport {
+                        enabled,
+                        state: CircuitState::Closed,
+                        recent_requests: 0,
+                        recent_success_rate: None,
+                        median_latency_ms: None,
+                        last_error: None,
+                    },
+                );
+            };
+
+            let mut latencies = health.recent.iter().flatten().collect::<Vec<_>>();
+            latencies.sort();
+            let recent_success_rate = if health.recent.is_empty() {
+                None
+            } else {
+                Some(latencies.len() as f64 / health.recent.len() as f64)
+            };
+            let median_latency_ms = latencies
+                .get(latencies.len() / 2)
+                .map(|latency| latency.as_millis() as u64);
+            (
+                engine,
+                EngineHealthReport {
+                    enabled,
+                    state: health.state(),
+                    recent_requests: health.recent.len(),
+                    recent_success_rate,
+                    median_latency_ms,
+                    last_error: health.last_error.clone(),
+                },
+            )
+        })
+        .collect()
+}
+
+/// Errors from reqwest include the url that was requested, which has the
+/// user's query in it, so it's removed before the error is shown to anyone.
+fn without_urls(message: &str) -> String {
+    let mut message = message.to_string();
+    while let Some(start) = message.find(" for url (") {
+        // spaces in urls are encoded, so the url ends at the first ")" that's
+        // followed by the end of the message or the next error in the chain
+        let end = message[start..]
+            .match_indices(')')
+            .map(|(i, _)| start + i + 1)
+            .find(|&end| message[end..].is_empty() || message[end..].starts_with(": "))
+            .unwrap_or(message.len());
+        message.replace_range(start..end, "");
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suspend_after_consecutive_failures() {
+        let config = CircuitBreakerConfig {
+            enabled: true,
+            failure_threshold: 2,
+            backoff_secs: 0,
+            max_backoff_secs: 0,
+        };
+        // a zero-length backoff means the suspension is over immediately, so we can
+        // check the half-open state without sleeping
+        let engine = &Engine::RightDao;
+
+        assert!(should_request(engine, &config));
+        record_failure(engine, &config, &eyre::eyre!("error"));
+        assert_eq!(HEALTH.lock()[engine].suspended_until, None);
+        record_failure(engine, &config, &eyre::eyre!("error"));
+        assert!(HEALTH.lock()[engine].suspended_until.is_some());
+
+        // only one probe is allowed through while half-open
+        assert!(should_request(engine, &config));
+        assert!(!should_request(engine, &config));
+
+        record_success(engine, Duration::from_millis(100));
+        assert_eq!(HEALTH.lock()[engine].state(), CircuitState::Closed);
+        assert!(should_request(engine, &config));
+    }
+
+    #[test]
+    fn test_without_urls() {
+        assert_eq!(
+            without_urls(
+                "error sending request for url (https://example.com/search?q=a):b): \
+                 client error (Connect): tcp connect error"
+            ),
+            "error sending request: client error (Connect): tcp connect error"
+        );
+        assert_eq!(
+            without_urls("HTTP status server error (503) for url (https://example.com/?q=(x))"),
+            "HTTP status server error (503)"
+        );
+    }
+}
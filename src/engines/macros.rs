@@ -1,21 +1,28 @@
 #[macro_export]
 macro_rules! engines {
     ($($engine:ident = $id:expr),* $(,)?) => {
-        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
         pub enum Engine {
             $($engine,)*
+            /// An engine that was defined in the `custom_engines` section of
+            /// the config.
+            #[serde(untagged)]
+            Custom(std::sync::Arc<str>),
         }
 
         impl Engine {
+            /// Every built-in engine. This doesn't include custom engines, use
+            /// [`Engine::all_with_custom`] for those.
             #[must_use]
             pub fn all() -> &'static [Engine] {
                 &[$(Engine::$engine,)*]
             }
 
             #[must_use]
-            pub fn id(&self) -> &'static str {
+            pub fn id(&self) -> &str {
                 match self {
                     $(Engine::$engine => $id,)*
+                    Engine::Custom(id) => id,
                 }
             }
         }
@@ -54,6 +61,7 @@ macro_rules! engine_requests {
                     $(
                         Engine::$engine => $module::$engine_id::$request(query).into(),
                     )*
+                    Engine::Custom(_) => $crate::engines::custom::request(self, query),
                     _ => RequestResponse::None,
                 }
             }
@@ -66,6 +74,7 @@ macro_rules! engine_requests {
                         Engine::$engine => $crate::engine_parse_response! { res, $module::$engine_id::$parse_response }
                             .ok_or_else(|| eyre::eyre!("engine {self:?} can't parse response"))?,
                     )*
+                    Engine::Custom(_) => $crate::engines::custom::parse_response(self, res),
                     _ => eyre::bail!("engine {self:?} can't parse response"),
                 }
             }
@@ -78,10 +87,10 @@ macro_rules! engine_autocomplete_requests {
     ($($engine:ident => $module:ident::$engine_id:ident::$request:ident, $parse_response:ident),* $(,)?) => {
         impl Engine {
             #[must_use]
-            pub fn request_autocomplete(&self, query: &str) -> Option<RequestAutocompleteResponse> {
+            pub fn request_autocomplete(&self, query: &str, config: &Config) -> Option<RequestAutocompleteResponse> {
                 match self {
                     $(
-                        Engine::$engine => Some($module::$engine_id::$request(query).into()),
+                        Engine::$engine => Some($module::$engine_id::$request(query, config).into()),
                     )*
                     _ => None,
                 }
@@ -154,3 +163,142 @@ macro_rules! engine_image_requests {
         }
     };
 }
+
+#[macro_export]
+macro_rules! engine_operators {
+    ($($engine:ident => [$($operator:ident),* $(,)?]),* $(,)?) => {
+        impl Engine {
+            /// The search operators that the engine supports natively. Other
+            /// operators are removed from the query that's sent to it.
+            #[must_use]
+            pub fn supported_operators(&self) -> &'static [Operator] {
+                match self {
+                    $(
+                        Engine::$engine => &[$(Operator::$operator),*],
+                    )*
+                    // we don't know what custom engines support, so they get the query as it was typed
+                    Engine::Custom(_) => Operator::ALL,
+                    _ => &[],
+                }
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! engine_news_requests {
+    ($($engine:ident => $module:ident::$engine_id:ident::$request:ident, $parse_response:ident),* $(,)?) => {
+        impl Engine {
+            #[must_use]
+            pub fn request_news(&self, query: &SearchQuery) -> RequestResponse {
+                match self {
+                    $(
+                        Engine::$engine => $module::$engine_id::$request(query).into(),
+                    )*
+                    _ => RequestResponse::None,
+                }
+            }
+
+            pub fn parse_news_response(&self, res: &HttpResponse) -> eyre::Result<EngineNewsResponse> {
+                #[allow(clippy::useless_conversion)]
+                match self {
+                    $(
+                        Engine::$engine => $crate::engine_parse_response! { res, $module::$engine_id::$parse_response }
+                            .ok_or_else(|| eyre::eyre!("engine {self:?} can't parse news response"))?,
+                    )*
+                    _ => eyre::bail!("engine {self:?} can't parse response"),
+                }
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! engine_video_requests {
+    ($($engine:ident => $module:ident::$engine_id:ident::$request:ident, $parse_response:ident),* $(,)?) => {
+        impl Engine {
+            #[must_use]
+            pub fn request_videos(&self, query: &SearchQuery) -> RequestResponse {
+                match self {
+                    $(
+                        Engine::$engine => $module::$engine_id::$request(query).into(),
+                    )*
+                    _ => RequestResponse::None,
+                }
+            }
+
+            pub fn parse_videos_response(&self, res: &HttpResponse) -> eyre::Result<EngineVideosResponse> {
+                #[allow(clippy::useless_conversion)]
+                match self {
+                    $(
+                        Engine::$engine => $crate::engine_parse_response! { res, $module::$engine_id::$parse_response }
+                            .ok_or_else(|| eyre::eyre!("engine {self:?} can't parse videos response"))?,
+                    )*
+                    _ => eyre::bail!("engine {self:?} can't parse response"),
+                }
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! engine_options {
+    ($($engine:ident => $module:ident::$engine_id:ident::$options:ident),* $(,)?) => {
+        /// The options that are specific to one engine. They're parsed from the
+        /// engine's section of the config when the config is loaded.
+        #[derive(Debug, Clone, Serialize)]
+        #[serde(untagged)]
+        pub enum EngineOptions {
+            $(
+                $engine($module::$engine_id::$options),
+            )*
+        }
+
+        impl Engine {
+            /// Parse the engine's options from the keys in its section of the
+            /// config that aren't shared by every engine. Options that aren't set
+            /// get their defaults. Returns None if the engine doesn't have any
+            /// options.
+            pub fn parse_options(
+                &self,
+                extra: &toml::Table,
+                on_unknown_key: impl FnMut(serde_ignored::Path),
+            ) -> Option<Result<EngineOptions, toml::de::Error>> {
+                match self {
+                    $(
+                        Engine::$engine => Some(
+                            serde_ignored::deserialize(toml::Value::Table(extra.clone()), on_unknown_key)
+                                .map(EngineOptions::$engine),
+                        ),
+                    )*
+                    _ => None,
+                }
+            }
+
+            #[must_use]
+            pub fn default_options(&self) -> Option<EngineOptions> {
+                match self {
+                    $(
+                        Engine::$engine => Some(EngineOptions::$engine(Default::default())),
+                    )*
+                    _ => None,
+                }
+            }
+        }
+
+        $(
+            impl $module::$engine_id::$options {
+                /// The engine's options from the config.
+                #[must_use]
+                pub fn get(config: &Config) -> &Self {
+                    static DEFAULT: LazyLock<$module::$engine_id::$options> =
+                        LazyLock::new(Default::default);
+                    match &config.engines.get(&Engine::$engine).options {
+                        Some(EngineOptions::$engine(options)) => options,
+                        _ => &DEFAULT,
+                    }
+                }
+            }
+        )*
+    };
+}
@@ -0,0 +1,398 @@
+//! Counters and histograms about engines, the cache, the image proxy, and
+//! autocomplete, which are exported at `/metrics` in the Prometheus text format.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{Display, Write},
+    sync::LazyLock,
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+
+use crate::config::Config;
+
+use super::{health, Engine, EngineProgressUpdate};
+
+static METRICS: LazyLock<Mutex<Metrics>> = LazyLock::new(|| Mutex::new(Metrics::default()));
+
+/// The upper bounds of the latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 9] = [0.05, 0.1, 0.25, 0.5, 1., 2.5, 5., 10., 30.];
+
+#[derive(Default)]
+struct Metrics {
+    engine_requests: BTreeMap<Engine, u64>,
+    engine_errors: BTreeMap<(Engine, ErrorKind), u64>,
+    engine_latency: BTreeMap<Engine, Histogram>,
+    engine_results: BTreeMap<Engine, u64>,
+    engine_empty_responses: BTreeMap<Engine, u64>,
+    cache_hits: u64,
+    cache_misses: u64,
+    image_proxy_requests: u64,
+    image_proxy_bytes: u64,
+    autocomplete_latency: Histogram,
+}
+
+#[derive(Default)]
+struct Histogram {
+    /// How many values were less than or equal to each bucket's upper bound.
+    bucket_counts: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: Duration) {
+        let secs = value.as_secs_f64();
+        for (&upper_bound, count) in LATENCY_BUCKETS.iter().zip(&mut self.bucket_counts) {
+            if secs <= upper_bound {
+                *count += 1;
+            }
+        }
+        self.sum += secs;
+        self.count += 1;
+    }
+}
+
+/// Why a request to an engine failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ErrorKind {
+    /// The engine took longer than its timeout or the search deadline.
+    Timeout,
+    /// We couldn't connect to the engine.
+    Connection,
+    /// Any other error while sending the request or downloading the response.
+    Request,
+    /// The response couldn't be parsed, which usually means that the engine
+    /// changed its HTML or is serving a captcha.
+    Parse,
+}
+
+impl ErrorKind {
+    /// Figure out why sending a request or downloading its response failed.
+    #[must_use]
+    pub fn of_request_error(err: &eyre::Report) -> Self {
+        match err.downcast_ref::<reqwest::Error>() {
+            Some(err) if err.is_timeout() => ErrorKind::Timeout,
+            Some(err) if err.is_connect() => ErrorKind::Connection,
+            _ => ErrorKind::Request,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Connection => "connection",
+            ErrorKind::Request => "request",
+            ErrorKind::Parse => "parse",
+        }
+    }
+}
+
+/// Counts requests to engines and measures how long they take, using the
+/// timings of the progress updates of a search.
+#[derive(Default)]
+pub struct RequestTimer {
+    /// When each engine started being requested, in milliseconds since the
+    /// search started.
+    started_ms: Mutex<HashMap<Engine, u64>>,
+}
+
+impl RequestTimer {
+    pub fn observe(&self, engine: &Engine, update: &EngineProgressUpdate, time_ms: u64) {
+        match update {
+            EngineProgressUpdate::Requesting => {
+                self.started_ms.lock().insert(engine.clone(), time_ms);
+                *METRICS
+                    .lock()
+                    .engine_requests
+                    .entry(engine.clone())
+                    .or_default() += 1;
+            }
+            EngineProgressUpdate::Done | EngineProgressUpdate::Error(_) => {
+                let Some(started_ms) = self.started_ms.lock().remove(engine) else {
+                    return;
+                };
+                let latency = Duration::from_millis(time_ms.saturating_sub(started_ms));
+                METRICS
+                    .lock()
+                    .engine_latency
+                    .entry(engine.clone())
+                    .or_default()
+                    .observe(latency);
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn record_error(engine: &Engine, kind: ErrorKind) {
+    *METRICS
+        .lock()
+        .engine_errors
+        .entry((engine.clone(), kind))
+        .or_default() += 1;
+}
+
+/// Record how many results the engine's response was parsed into.
+pub fn record_results(engine: &Engine, count: usize) {
+    let mut metrics = METRICS.lock();
+    *metrics.engine_results.entry(engine.clone()).or_default() += count as u64;
+    if count == 0 {
+        *metrics
+            .engine_empty_responses
+            .entry(engine.clone())
+            .or_default() += 1;
+    }
+}
+
+pub fn record_cache_lookup(hit: bool) {
+    let mut metrics = METRICS.lock();
+    if hit {
+        metrics.cache_hits += 1;
+    } else {
+        metrics.cache_misses += 1;
+    }
+}
+
+pub fn record_image_proxy(bytes: usize) {
+    let mut metrics = METRICS.lock();
+    metrics.image_proxy_requests += 1;
+    metrics.image_proxy_bytes += bytes as u64;
+}
+
+pub fn record_autocomplete(latency: Duration) {
+    METRICS.lock().autocomplete_latency.observe(latency);
+}
+
+/// Write every metric in the Prometheus text format.
+#[must_use]
+pub fn render(config: &Config) -> String {
+    let metrics = METRICS.lock();
+    let mut out = String::new();
+
+    write_header(
+        &mut out,
+        "metasearch_engine_requests_total",
+        "counter",
+        "Requests that were sent to each engine.",
+    );
+    for (engine, count) in &metrics.engine_requests {
+        write_sample(
+            &mut out,
+            "metasearch_engine_requests_total",
+            &[("engine", engine.id())],
+            count,
+        );
+    }
+
+    write_header(
+        &mut out,
+        "metasearch_engine_errors_total",
+        "counter",
+        "Requests to each engine that failed, by the kind of error.",
+    );
+    for ((engine, kind), count) in &metrics.engine_errors {
+        write_sample(
+            &mut out,
+            "metasearch_engine_errors_total",
+            &[("engine", engine.id()), ("kind", kind.as_str())],
+            count,
+        );
+    }
+
+    write_header(
+        &mut out,
+        "metasearch_engine_request_duration_seconds",
+        "histogram",
+        "How long engines took to respond, including failed requests.",
+    );
+    for (engine, histogram) in &metrics.engine_latency {
+        write_histogram(
+            &mut out,
+            "metasearch_engine_request_duration_seconds",
+            &[("engine", engine.id())],
+            histogram,
+        );
+    }
+
+    write_header(
+        &mut out,
+        "metasearch_engine_results_total",
+        "counter",
+        "Results that were parsed from each engine's responses, including answers and infoboxes.",
+    );
+    for (engine, count) in &metrics.engine_results {
+        write_sample(
+            &mut out,
+            "metasearch_engine_results_total",
+            &[("engine", engine.id())],
+            count,
+        );
+    }
+
+    write_header(
+        &mut out,
+        "metasearch_engine_empty_responses_total",
+        "counter",
+        "Responses from each engine that were parsed without any results.",
+    );
+    for (engine, count) in &metrics.engine_empty_responses {
+        write_sample(
+            &mut out,
+            "metasearch_engine_empty_responses_total",
+            &[("engine", engine.id())],
+            count,
+        );
+    }
+
+    write_header(
+        &mut out,
+        "metasearch_engine_suspended",
+        "gauge",
+        "Whether each engine is suspended by the circuit breaker.",
+    );
+    for (engine, status) in health::statuses(config) {
+        let suspended = status.state == health::CircuitState::Open;
+        write_sample(
+            &mut out,
+            "metasearch_engine_suspended",
+            &[("engine", engine.id())],
+            u8::from(suspended),
+        );
+    }
+
+    write_header(
+        &mut out,
+        "metasearch_cache_lookups_total",
+        "counter",
+        "Searches that were looked up in the cache, by whether they were found.",
+    );
+    write_sample(
+        &mut out,
+        "metasearch_cache_lookups_total",
+        &[("result", "hit")],
+        metrics.cache_hits,
+    );
+    write_sample(
+        &mut out,
+        "metasearch_cache_lookups_total",
+        &[("result", "miss")],
+        metrics.cache_misses,
+    );
+
+    write_header(
+        &mut out,
+        "metasearch_image_proxy_requests_total",
+        "counter",
+        "Images that were sent through the image proxy.",
+    );
+    write_sample(
+        &mut out,
+        "metasearch_image_proxy_requests_total",
+        &[],
+        metrics.image_proxy_requests,
+    );
+    write_header(
+        &mut out,
+        "metasearch_image_proxy_bytes_total",
+        "counter",
+        "Bytes of images that were sent through the image proxy.",
+    );
+    write_sample(
+        &mut out,
+        "metasearch_image_proxy_bytes_total",
+        &[],
+        metrics.image_proxy_bytes,
+    );
+
+    write_header(
+        &mut out,
+        "metasearch_autocomplete_duration_seconds",
+        "histogram",
+        "How long autocomplete requests took.",
+    );
+    write_histogram(
+        &mut out,
+        "metasearch_autocomplete_duration_seconds",
+        &[],
+        &metrics.autocomplete_latency,
+    );
+
+    out
+}
+
+fn write_header(out: &mut String, name: &str, metric_type: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {metric_type}");
+}
+
+fn write_sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: impl Display) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        let labels = labels
+            .iter()
+            .map(|(label, value)| format!("{label}=\"{}\"", escape_label_value(value)))
+            .collect::<Vec<_>>()
+            .join(",");
+        let _ = write!(out, "{{{labels}}}");
+    }
+    let _ = writeln!(out, " {value}");
+}
+
+fn write_histogram(out: &mut String, name: &str, labels: &[(&str, &str)], histogram: &Histogram) {
+    let bucket_name = format!("{name}_bucket");
+    for (upper_bound, count) in LATENCY_BUCKETS.iter().zip(histogram.bucket_counts) {
+        let upper_bound = upper_bound.to_string();
+        let bucket_labels = [labels, &[("le", upper_bound.as_str())]].concat();
+        write_sample(out, &bucket_name, &bucket_labels, count);
+    }
+    let bucket_labels = [labels, &[("le", "+Inf")]].concat();
+    write_sample(out, &bucket_name, &bucket_labels, histogram.count);
+    write_sample(out, &format!("{name}_sum"), labels, histogram.sum);
+    write_sample(out, &format!("{name}_count"), labels, histogram.count);
+}
+
+/// Custom engines can have any name, so their ids have to be escaped.
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_histogram() {
+        let mut histogram = Histogram::default();
+        histogram.observe(Duration::from_millis(80));
+        histogram.observe(Duration::from_millis(700));
+        histogram.observe(Duration::from_secs(60));
+
+        let mut out = String::new();
+        write_histogram(
+            &mut out,
+            "latency_seconds",
+            &[("engine", "a\"b")],
+            &histogram,
+        );
+        assert_eq!(
+            out,
+            "latency_seconds_bucket{engine=\"a\\\"b\",le=\"0.05\"} 0\n\
+             latency_seconds_bucket{engine=\"a\\\"b\",le=\"0.1\"} 1\n\
+             latency_seconds_bucket{engine=\"a\\\"b\",le=\"0.25\"} 1\n\
+             latency_seconds_bucket{engine=\"a\\\"b\",le=\"0.5\"} 1\n\
+             latency_seconds_bucket{engine=\"a\\\"b\",le=\"1\"} 2\n\
+             latency_seconds_bucket{engine=\"a\\\"b\",le=\"2.5\"} 2\n\
+             latency_seconds_bucket{engine=\"a\\\"b\",le=\"5\"} 2\n\
+             latency_seconds_bucket{engine=\"a\\\"b\",le=\"10\"} 2\n\
+             latency_seconds_bucket{engine=\"a\\\"b\",le=\"30\"} 2\n\
+             latency_seconds_bucket{engine=\"a\\\"b\",le=\"+Inf\"} 3\n\
+             latency_seconds_sum{engine=\"a\\\"b\"} 60.78\n\
+             latency_seconds_count{engine=\"a\\\"b\"} 3\n"
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     fmt::{self, Display},
     net::IpAddr,
     ops::Deref,
@@ -8,20 +8,33 @@ use std::{
     time::{Duration, Instant},
 };
 
-use eyre::bail;
-use futures::future::join_all;
+use chrono::{DateTime, Utc};
+use eyre::{bail, eyre};
+use futures::{future::join_all, stream::FuturesUnordered, Future, StreamExt};
 use maud::PreEscaped;
-use reqwest::{header::HeaderMap, RequestBuilder};
+use parking_lot::Mutex;
+use reqwest::{
+    header::{self, HeaderMap, HeaderValue},
+    RequestBuilder,
+};
 use serde::{Deserialize, Deserializer, Serialize};
 use tokio::sync::mpsc;
 use tracing::{error, info};
 
+mod cache;
+pub mod custom;
+pub mod fixtures;
+pub mod health;
 mod macros;
+pub mod metrics;
+pub mod operators;
 mod ranking;
 use crate::{
-    config::Config, engine_autocomplete_requests, engine_image_requests,
-    engine_postsearch_requests, engine_requests, engines,
+    config::Config, engine_autocomplete_requests, engine_image_requests, engine_news_requests,
+    engine_operators, engine_options, engine_postsearch_requests, engine_requests,
+    engine_video_requests, engines,
 };
+use operators::{Operator, ParsedQuery};
 
 pub mod answer;
 pub mod postsearch;
@@ -37,6 +50,7 @@ engines! {
     RightDao = "rightdao",
     Stract = "stract",
     Yep = "yep",
+    Invidious = "invidious",
     // answer
     Dictionary = "dictionary",
     Fend = "fend",
@@ -98,6 +112,82 @@ engine_image_requests! {
     Bing => search::bing::request_images, parse_images_response,
 }
 
+engine_news_requests! {
+    Google => search::google::request_news, parse_news_response,
+    Bing => search::bing::request_news, parse_news_response,
+    Brave => search::brave::request_news, parse_news_response,
+}
+
+engine_video_requests! {
+    Bing => search::bing::request_videos, parse_videos_response,
+    Brave => search::brave::request_videos, parse_videos_response,
+    Invidious => search::invidious::request_videos, parse_videos_response,
+}
+
+engine_options! {
+    Marginalia => search::marginalia::MarginaliaConfig,
+    Invidious => search::invidious::InvidiousConfig,
+    Mdn => postsearch::mdn::MdnConfig,
+}
+
+engine_operators! {
+    Google => [Phrase, Exclude, Site, ExcludeSite, Filetype],
+    GoogleScholar => [Phrase, Exclude, Site],
+    Bing => [Phrase, Exclude, Site, ExcludeSite, Filetype],
+    // brave doesn't support exact matching anymore
+    Brave => [Exclude, Site, ExcludeSite, Filetype],
+    Marginalia => [Phrase, Exclude, Site],
+    Stract => [Phrase, Exclude, Site, ExcludeSite],
+}
+
+impl Engine {
+    /// Whether the engine's search results can be filtered by time. Engines
+    /// that can't are skipped when a time range is set, but answer engines are
+    /// still used since they don't return search results.
+    #[must_use]
+    pub fn can_filter_by_time(&self, config: &Config) -> bool {
+        match self {
+            Engine::Google | Engine::Bing | Engine::Brave | Engine::Invidious => true,
+            Engine::GoogleScholar
+            | Engine::Marginalia
+            | Engine::RightDao
+            | Engine::Stract
+            | Engine::Yep => false,
+            Engine::Custom(_) => config.engines.custom.get(self).is_some_and(|custom| {
+                custom.url.contains("{time_range}")
+                    || custom
+                        .body
+                        .as_ref()
+                        .is_some_and(|body| body.contains("{time_range}"))
+            }),
+            _ => true,
+        }
+    }
+
+    /// Whether the engine's answer can be cached. Answers that depend on who's
+    /// searching or on the current time, like "utc time" or "now" in the
+    /// calculators, can't be.
+    #[must_use]
+    pub fn answer_is_cacheable(&self) -> bool {
+        !matches!(
+            self,
+            Engine::Ip | Engine::Useragent | Engine::Timezone | Engine::Fend | Engine::Numbat
+        )
+    }
+
+    /// Every built-in engine, followed by the custom engines from the config.
+    #[must_use]
+    pub fn all_with_custom(config: &Config) -> Vec<Engine> {
+        let mut custom_engines = config.engines.custom.keys().cloned().collect::<Vec<_>>();
+        custom_engines.sort();
+        Engine::all()
+            .iter()
+            .cloned()
+            .chain(custom_engines)
+            .collect()
+    }
+}
+
 impl fmt::Display for Engine {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.id())
@@ -114,9 +204,23 @@ impl<'de> Deserialize<'de> for Engine {
     }
 }
 
+/// The last page of results that can be requested. Engines stop returning
+/// results long before this anyway.
+pub const MAX_PAGE: usize = 50;
+
 pub struct SearchQuery {
     pub query: String,
     pub tab: SearchTab,
+    /// The page of results that was requested, from 1 to [`MAX_PAGE`].
+    pub page: usize,
+    /// The query with its search operators parsed.
+    pub parsed: ParsedQuery,
+    /// Only show results from this time range. This is used for every tab
+    /// except images.
+    pub time_range: Option<TimeRange>,
+    /// The engines that were chosen for this query, on top of the ones that
+    /// are enabled in the config.
+    pub engine_selection: EngineSelection,
     pub request_headers: HashMap<String, String>,
     pub ip: String,
     /// The config is part of the query so it's possible to make a query with a
@@ -124,6 +228,30 @@ pub struct SearchQuery {
     pub config: Arc<Config>,
 }
 
+impl SearchQuery {
+    /// The number of results that come before this page, assuming the engine
+    /// returns `per_page` results per page.
+    #[must_use]
+    pub fn offset(&self, per_page: usize) -> usize {
+        self.page.saturating_sub(1).saturating_mul(per_page)
+    }
+
+    /// The query that should be sent to the engine, with the operators that it
+    /// doesn't support removed.
+    #[must_use]
+    pub fn engine_query(&self, engine: &Engine) -> String {
+        self.parsed.to_engine_query(engine.supported_operators())
+    }
+
+    /// Whether the engine should be used for this query, which depends on the
+    /// config and the engine selection.
+    #[must_use]
+    pub fn is_engine_enabled(&self, engine: &Engine) -> bool {
+        self.engine_selection
+            .is_enabled(engine, self.config.engines.get(engine).enabled)
+    }
+}
+
 impl Deref for SearchQuery {
     type Target = str;
 
@@ -132,11 +260,20 @@ impl Deref for SearchQuery {
     }
 }
 
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SearchTab {
     #[default]
     All,
     Images,
+    News,
+    Videos,
+}
+impl SearchTab {
+    /// Whether results in the tab can be filtered with a [`TimeRange`].
+    #[must_use]
+    pub fn has_time_range(&self) -> bool {
+        matches!(self, Self::All | Self::News | Self::Videos)
+    }
 }
 impl FromStr for SearchTab {
     type Err = ();
@@ -144,6 +281,8 @@ impl FromStr for SearchTab {
         match s {
             "all" => Ok(Self::All),
             "images" => Ok(Self::Images),
+            "news" => Ok(Self::News),
+            "videos" => Ok(Self::Videos),
             _ => Err(()),
         }
     }
@@ -153,10 +292,161 @@ impl Display for SearchTab {
         match self {
             Self::All => write!(f, "all"),
             Self::Images => write!(f, "images"),
+            Self::News => write!(f, "news"),
+            Self::Videos => write!(f, "videos"),
+        }
+    }
+}
+
+/// How recent the results should be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeRange {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+impl TimeRange {
+    pub const ALL: [TimeRange; 4] = [Self::Day, Self::Week, Self::Month, Self::Year];
+
+    /// How far back the time range goes.
+    #[must_use]
+    pub fn duration(&self) -> chrono::Duration {
+        match self {
+            Self::Day => chrono::Duration::days(1),
+            Self::Week => chrono::Duration::weeks(1),
+            Self::Month => chrono::Duration::days(31),
+            Self::Year => chrono::Duration::days(366),
+        }
+    }
+}
+impl FromStr for TimeRange {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            "year" => Ok(Self::Year),
+            _ => Err(()),
+        }
+    }
+}
+impl Display for TimeRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Day => write!(f, "day"),
+            Self::Week => write!(f, "week"),
+            Self::Month => write!(f, "month"),
+            Self::Year => write!(f, "year"),
         }
     }
 }
 
+/// Engines that were chosen for a single query, like `google,marginalia` to
+/// only use those engines or `-bing` to use every engine except Bing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct EngineSelection {
+    /// If this is set, then these are the only engines that are used. It's
+    /// empty if none of the chosen engines exist or are enabled, in which case
+    /// no engines are used.
+    pub only: Option<Vec<Engine>>,
+    /// The chosen ids that aren't enabled engines. They're kept so links to
+    /// other pages and tabs have the same selection.
+    pub unmatched: Vec<String>,
+    pub exclude: Vec<Engine>,
+}
+impl EngineSelection {
+    /// Parse a comma-separated list of engine ids, where ids that start with
+    /// `-` are excluded. Engines that don't exist or are disabled in the
+    /// config can't be chosen, and are put in `unmatched` instead.
+    #[must_use]
+    pub fn parse(s: &str, config: &Config) -> Self {
+        let engines = Engine::all_with_custom(config)
+            .into_iter()
+            .filter(|engine| config.engines.get(engine).enabled)
+            .collect::<Vec<_>>();
+        let find_engine = |id: &str| {
+            let id = id.trim().to_lowercase();
+            engines.iter().find(|engine| engine.id() == id).cloned()
+        };
+
+        let mut selection = Self::default();
+        for id in s.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+            match id.strip_prefix('-') {
+                // excluding an engine that isn't used anyway doesn't change anything
+                Some(id) => {
+                    if let Some(engine) = find_engine(id) {
+                        if !selection.exclude.contains(&engine) {
+                            selection.exclude.push(engine);
+                        }
+                    }
+                }
+                None => {
+                    let only = selection.only.get_or_insert_with(Vec::new);
+                    match find_engine(id) {
+                        Some(engine) => {
+                            if !only.contains(&engine) {
+                                only.push(engine);
+                            }
+                        }
+                        None => {
+                            let id = id.to_lowercase();
+                            if !selection.unmatched.contains(&id) {
+                                selection.unmatched.push(id);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        selection
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.only.is_none() && self.exclude.is_empty()
+    }
+
+    /// Whether the engine should be used, given whether it's enabled in the
+    /// config. Engines that are disabled in the config are never used, even if
+    /// they were selected.
+    #[must_use]
+    pub fn is_enabled(&self, engine: &Engine, enabled_in_config: bool) -> bool {
+        enabled_in_config
+            && !self.exclude.contains(engine)
+            && self
+                .only
+                .as_ref()
+                .is_none_or(|only| only.contains(engine))
+    }
+}
+impl Display for EngineSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ids = self
+            .only
+            .iter()
+            .flatten()
+            .map(|engine| engine.id().to_string())
+            .chain(self.unmatched.iter().cloned())
+            .chain(
+                self.exclude
+                    .iter()
+                    .map(|engine| format!("-{}", engine.id())),
+            )
+            .collect::<Vec<_>>();
+        write!(f, "{}", ids.join(","))
+    }
+}
+impl Serialize for EngineSelection {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
 pub enum RequestResponse {
     None,
     Http(reqwest::RequestBuilder),
@@ -213,7 +503,7 @@ pub struct EngineSearchResult {
     pub description: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct EngineFeaturedSnippet {
     pub url: String,
     pub title: String,
@@ -254,6 +544,16 @@ impl EngineResponse {
             ..Default::default()
         }
     }
+
+    /// How many results the response has, where the featured snippet, answer,
+    /// and infobox each count as one.
+    #[must_use]
+    pub fn result_count(&self) -> usize {
+        self.search_results.len()
+            + usize::from(self.featured_snippet.is_some())
+            + usize::from(self.answer_html.is_some())
+            + usize::from(self.infobox_html.is_some())
+    }
 }
 
 impl EngineImagesResponse {
@@ -263,6 +563,30 @@ impl EngineImagesResponse {
     }
 }
 
+#[derive(Default)]
+pub struct EngineNewsResponse {
+    pub news_results: Vec<EngineNewsResult>,
+}
+
+impl EngineNewsResponse {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Default)]
+pub struct EngineVideosResponse {
+    pub video_results: Vec<EngineVideoResult>,
+}
+
+impl EngineVideosResponse {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct EngineImageResult {
     pub image_url: String,
@@ -272,6 +596,30 @@ pub struct EngineImageResult {
     pub height: u64,
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineNewsResult {
+    pub url: String,
+    pub title: String,
+    pub description: String,
+    /// The name of the site that published the article, like "BBC News".
+    pub source: String,
+    pub published: Option<DateTime<Utc>>,
+    pub thumbnail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineVideoResult {
+    pub url: String,
+    pub title: String,
+    pub description: String,
+    /// The name of the channel that uploaded the video.
+    pub channel: String,
+    /// The length of the video in seconds.
+    pub duration: Option<u64>,
+    pub published: Option<DateTime<Utc>>,
+    pub thumbnail: Option<String>,
+}
+
 #[derive(Debug)]
 pub enum EngineProgressUpdate {
     Requesting,
@@ -279,6 +627,10 @@ pub enum EngineProgressUpdate {
     Parsing,
     Done,
     Error(String),
+    /// The engine failed too many times recently, so it wasn't requested.
+    Suspended,
+    /// The engine can't filter its results by time, so it wasn't requested.
+    NoTimeRange,
 }
 
 #[derive(Debug)]
@@ -287,7 +639,10 @@ pub enum ProgressUpdateData {
         engine: Engine,
         update: EngineProgressUpdate,
     },
-    Response(ResponseForTab),
+    /// The results are being served from the cache, so no engines will be
+    /// requested.
+    CacheHit,
+    Response(Box<ResponseForTab>),
     PostSearchInfobox(Infobox),
 }
 
@@ -309,23 +664,24 @@ impl ProgressUpdate {
 
 async fn make_request(
     request: RequestBuilder,
-    engine: Engine,
+    engine: &Engine,
     query: &SearchQuery,
     send_engine_progress_update: impl Fn(Engine, EngineProgressUpdate),
 ) -> eyre::Result<HttpResponse> {
-    send_engine_progress_update(engine, EngineProgressUpdate::Requesting);
+    send_engine_progress_update(engine.clone(), EngineProgressUpdate::Requesting);
 
-    let mut res = request.send().await?;
+    let mut res = send_engine_request(request, engine, &query.config).await?;
 
-    send_engine_progress_update(engine, EngineProgressUpdate::Downloading);
+    send_engine_progress_update(engine.clone(), EngineProgressUpdate::Downloading);
 
     let mut body_bytes = Vec::new();
     while let Some(chunk) = res.chunk().await? {
         body_bytes.extend_from_slice(&chunk);
     }
     let body = String::from_utf8_lossy(&body_bytes).to_string();
+    fixtures::record(engine, query.tab.into(), query, &res, &body);
 
-    send_engine_progress_update(engine, EngineProgressUpdate::Parsing);
+    send_engine_progress_update(engine.clone(), EngineProgressUpdate::Parsing);
 
     let http_response = HttpResponse {
         res,
@@ -335,6 +691,76 @@ async fn make_request(
     Ok(http_response)
 }
 
+/// Override the client's default timeout if the engine has its own.
+fn with_engine_timeout(
+    request: RequestBuilder,
+    engine: &Engine,
+    config: &Config,
+) -> RequestBuilder {
+    match config.engines.get(engine).timeout_ms {
+        Some(timeout_ms) => request.timeout(Duration::from_millis(timeout_ms)),
+        None => request,
+    }
+}
+
+/// Wait for every engine to respond or for the search deadline to pass,
+/// whichever happens first. Engines that haven't responded by the deadline are
+/// reported as timed out and left out of the results.
+async fn join_engine_requests<R>(
+    requests: Vec<(Engine, impl Future<Output = (Engine, eyre::Result<R>)>)>,
+    query: &SearchQuery,
+    start_time: Instant,
+    send_engine_progress_update: &impl Fn(Engine, EngineProgressUpdate),
+) -> HashMap<Engine, R> {
+    let mut unfinished_engines = Vec::new();
+    let mut response_futures = FuturesUnordered::new();
+    for (engine, request) in requests {
+        unfinished_engines.push(engine);
+        response_futures.push(request);
+    }
+
+    let deadline = tokio::time::sleep_until(
+        (start_time + Duration::from_millis(query.config.search_deadline_ms)).into(),
+    );
+    tokio::pin!(deadline);
+
+    let mut responses = HashMap::new();
+    loop {
+        tokio::select! {
+            response = response_futures.next() => {
+                let Some((engine, response_result)) = response else {
+                    // every engine is done
+                    break;
+                };
+                unfinished_engines.retain(|e| *e != engine);
+                if let Ok(response) = response_result {
+                    responses.insert(engine, response);
+                }
+            }
+            _ = &mut deadline => {
+                for engine in &unfinished_engines {
+                    // the circuit breaker only counts web searches, see make_tab_requests
+                    if query.tab == SearchTab::All {
+                        health::record_failure(
+                            engine,
+                            &query.config.circuit_breaker,
+                            &eyre!("timed out"),
+                        );
+                    }
+                    metrics::record_error(engine, metrics::ErrorKind::Timeout);
+                    send_engine_progress_update(
+                        engine.clone(),
+                        EngineProgressUpdate::Error("timed out".to_string()),
+                    );
+                }
+                break;
+            }
+        }
+    }
+
+    responses
+}
+
 async fn make_requests(
     query: &SearchQuery,
     progress_tx: &mpsc::UnboundedSender<ProgressUpdate>,
@@ -342,44 +768,74 @@ async fn make_requests(
     send_engine_progress_update: &impl Fn(Engine, EngineProgressUpdate),
 ) -> eyre::Result<()> {
     let mut requests = Vec::new();
-    for &engine in Engine::all() {
-        let engine_config = query.config.engines.get(engine);
-        if !engine_config.enabled {
+    for engine in Engine::all_with_custom(&query.config) {
+        if !query.is_engine_enabled(&engine) {
+            continue;
+        }
+        if query.time_range.is_some() && !engine.can_filter_by_time(&query.config) {
+            send_engine_progress_update(engine.clone(), EngineProgressUpdate::NoTimeRange);
             continue;
         }
 
-        requests.push(async move {
-            let request_response = engine.request(query);
+        requests.push((engine.clone(), async move {
+            // a query that's only operators the engine doesn't support, like
+            // `site:docs.rs`, would be sent to it as an empty query
+            let request_response = if query.engine_query(&engine).is_empty() {
+                RequestResponse::None
+            } else {
+                engine.request(query)
+            };
 
             let response = match request_response {
                 RequestResponse::Http(request) => {
+                    let circuit_breaker_config = &query.config.circuit_breaker;
+                    if !health::should_request(&engine, circuit_breaker_config) {
+                        send_engine_progress_update(
+                            engine.clone(),
+                            EngineProgressUpdate::Suspended,
+                        );
+                        return (engine, Err(eyre!("engine is suspended")));
+                    }
+
+                    let request_start_time = Instant::now();
                     let http_response =
-                        match make_request(request, engine, query, send_engine_progress_update)
+                        match make_request(request, &engine, query, send_engine_progress_update)
                             .await
                         {
                             Ok(http_response) => http_response,
                             Err(e) => {
+                                health::record_failure(&engine, circuit_breaker_config, &e);
+                                metrics::record_error(
+                                    &engine,
+                                    metrics::ErrorKind::of_request_error(&e),
+                                );
                                 send_engine_progress_update(
-                                    engine,
+                                    engine.clone(),
                                     EngineProgressUpdate::Error(e.to_string()),
                                 );
-                                return Err(e);
+                                return (engine, Err(e));
                             }
                         };
 
                     let response = match engine.parse_response(&http_response) {
-                        Ok(response) => response,
+                        Ok(response) => {
+                            metrics::record_results(&engine, response.result_count());
+                            response
+                        }
                         Err(e) => {
                             error!("parse error for {engine}: {e}");
+                            metrics::record_error(&engine, metrics::ErrorKind::Parse);
+                            health::record_failure(&engine, circuit_breaker_config, &e);
                             send_engine_progress_update(
-                                engine,
+                                engine.clone(),
                                 EngineProgressUpdate::Error(e.to_string()),
                             );
-                            return Err(e);
+                            return (engine, Err(e));
                         }
                     };
 
-                    send_engine_progress_update(engine, EngineProgressUpdate::Done);
+                    health::record_success(&engine, request_start_time.elapsed());
+                    send_engine_progress_update(engine.clone(), EngineProgressUpdate::Done);
 
                     response
                 }
@@ -387,49 +843,52 @@ async fn make_requests(
                 RequestResponse::None => EngineResponse::new(),
             };
 
-            Ok((engine, response))
-        });
-    }
-
-    let mut response_futures = Vec::new();
-    for request in requests {
-        response_futures.push(request);
+            (engine, Ok(response))
+        }));
     }
 
-    let mut responses = HashMap::new();
-    for response_result in join_all(response_futures).await {
-        let response_result: eyre::Result<_> = response_result; // this line is necessary to make type inference work
-        if let Ok((engine, response)) = response_result {
-            responses.insert(engine, response);
-        }
-    }
+    let request_count = requests.len();
+    let responses =
+        join_engine_requests(requests, query, start_time, send_engine_progress_update).await;
+    // don't cache the results if an engine failed or timed out
+    let is_complete = responses.len() == request_count;
 
-    let response = ranking::merge_engine_responses(query.config.clone(), responses);
-    let has_infobox = response.infobox.is_some();
+    let response = ranking::merge_engine_responses(query, responses);
+    // infoboxes are only shown on the first page
+    let has_infobox = response.infobox.is_some() || query.page > 1;
     progress_tx.send(ProgressUpdate::new(
-        ProgressUpdateData::Response(ResponseForTab::All(response.clone())),
+        ProgressUpdateData::Response(Box::new(ResponseForTab::All(response.clone()))),
         start_time,
     ))?;
 
+    let mut postsearch_infobox = None;
+
     if !has_infobox {
         // post-search
 
         let mut postsearch_requests = Vec::new();
-        for &engine in Engine::all() {
-            let engine_config = query.config.engines.get(engine);
-            if !engine_config.enabled {
+        for engine in Engine::all().iter().cloned() {
+            if !query.is_engine_enabled(&engine) {
                 continue;
             }
 
             if let Some(request) = engine.postsearch_request(&response) {
                 postsearch_requests.push(async move {
-                    let response = match request.send().await {
+                    let response = match send_engine_request(request, &engine, &query.config).await
+                    {
                         Ok(mut res) => {
                             let mut body_bytes = Vec::new();
                             while let Some(chunk) = res.chunk().await? {
                                 body_bytes.extend_from_slice(&chunk);
                             }
                             let body = String::from_utf8_lossy(&body_bytes).to_string();
+                            fixtures::record(
+                                &engine,
+                                fixtures::FixtureKind::PostSearch,
+                                query,
+                                &res,
+                                &body,
+                            );
 
                             let http_response = HttpResponse {
                                 res,
@@ -462,78 +921,188 @@ async fn make_requests(
 
         for (engine, response) in postsearch_responses {
             if let Some(html) = response {
+                let infobox = Infobox { html, engine };
                 progress_tx.send(ProgressUpdate::new(
-                    ProgressUpdateData::PostSearchInfobox(Infobox { html, engine }),
+                    ProgressUpdateData::PostSearchInfobox(infobox.clone()),
                     start_time,
                 ))?;
+                postsearch_infobox = Some(infobox);
                 // break so we don't send multiple infoboxes
                 break;
             }
         }
     }
 
+    if is_complete {
+        cache::insert(
+            query,
+            cache::CachedSearch {
+                response: ResponseForTab::All(response),
+                infobox: postsearch_infobox,
+            },
+        );
+    }
+
     Ok(())
 }
 
-async fn make_image_requests(
+/// The response from an engine for a tab that only has one kind of result, like
+/// images or news. The requests for these tabs are all made the same way.
+trait TabResponse: Default {
+    /// What the results are called, for logging.
+    const KIND: &'static str;
+
+    fn request(engine: &Engine, query: &SearchQuery) -> RequestResponse;
+    fn parse_response(engine: &Engine, res: &HttpResponse) -> eyre::Result<Self>;
+    fn result_count(&self) -> usize;
+    fn merge(query: &SearchQuery, responses: HashMap<Engine, Self>) -> ResponseForTab;
+}
+
+impl TabResponse for EngineImagesResponse {
+    const KIND: &'static str = "images";
+
+    fn request(engine: &Engine, query: &SearchQuery) -> RequestResponse {
+        engine.request_images(query)
+    }
+    fn parse_response(engine: &Engine, res: &HttpResponse) -> eyre::Result<Self> {
+        engine.parse_images_response(res)
+    }
+    fn result_count(&self) -> usize {
+        self.image_results.len()
+    }
+    fn merge(query: &SearchQuery, responses: HashMap<Engine, Self>) -> ResponseForTab {
+        ResponseForTab::Images(ranking::merge_images_responses(query, responses))
+    }
+}
+
+impl TabResponse for EngineNewsResponse {
+    const KIND: &'static str = "news";
+
+    fn request(engine: &Engine, query: &SearchQuery) -> RequestResponse {
+        engine.request_news(query)
+    }
+    fn parse_response(engine: &Engine, res: &HttpResponse) -> eyre::Result<Self> {
+        engine.parse_news_response(res)
+    }
+    fn result_count(&self) -> usize {
+        self.news_results.len()
+    }
+    fn merge(query: &SearchQuery, responses: HashMap<Engine, Self>) -> ResponseForTab {
+        ResponseForTab::News(ranking::merge_news_responses(query, responses))
+    }
+}
+
+impl TabResponse for EngineVideosResponse {
+    const KIND: &'static str = "videos";
+
+    fn request(engine: &Engine, query: &SearchQuery) -> RequestResponse {
+        engine.request_videos(query)
+    }
+    fn parse_response(engine: &Engine, res: &HttpResponse) -> eyre::Result<Self> {
+        engine.parse_videos_response(res)
+    }
+    fn result_count(&self) -> usize {
+        self.video_results.len()
+    }
+    fn merge(query: &SearchQuery, responses: HashMap<Engine, Self>) -> ResponseForTab {
+        ResponseForTab::Videos(ranking::merge_videos_responses(query, responses))
+    }
+}
+
+async fn make_tab_requests<R: TabResponse>(
     query: &SearchQuery,
     progress_tx: &mpsc::UnboundedSender<ProgressUpdate>,
     start_time: Instant,
     send_engine_progress_update: &impl Fn(Engine, EngineProgressUpdate),
 ) -> eyre::Result<()> {
     let mut requests = Vec::new();
-    for &engine in Engine::all() {
-        let engine_config = query.config.engines.get(engine);
-        if !engine_config.enabled {
+    for engine in Engine::all().iter().cloned() {
+        if !query.is_engine_enabled(&engine) {
             continue;
         }
 
-        requests.push(async move {
-            let request_response = engine.request_images(query);
+        requests.push((engine.clone(), async move {
+            let request_response = if query.engine_query(&engine).is_empty() {
+                RequestResponse::None
+            } else {
+                R::request(&engine, query)
+            };
 
+            // the circuit breaker only tracks web searches, since an engine's other
+            // tabs failing doesn't mean that its web search is broken
             let response = match request_response {
                 RequestResponse::Http(request) => {
                     let http_response =
-                        make_request(request, engine, query, send_engine_progress_update).await?;
+                        match make_request(request, &engine, query, send_engine_progress_update)
+                            .await
+                        {
+                            Ok(http_response) => http_response,
+                            Err(e) => {
+                                metrics::record_error(
+                                    &engine,
+                                    metrics::ErrorKind::of_request_error(&e),
+                                );
+                                send_engine_progress_update(
+                                    engine.clone(),
+                                    EngineProgressUpdate::Error(e.to_string()),
+                                );
+                                return (engine, Err(e));
+                            }
+                        };
 
-                    let response = match engine.parse_images_response(&http_response) {
-                        Ok(response) => response,
+                    let response = match R::parse_response(&engine, &http_response) {
+                        Ok(response) => {
+                            metrics::record_results(&engine, response.result_count());
+                            response
+                        }
                         Err(e) => {
-                            error!("parse error for {engine} (images): {e}");
-                            EngineImagesResponse::new()
+                            error!("parse error for {engine} ({}): {e}", R::KIND);
+                            metrics::record_error(&engine, metrics::ErrorKind::Parse);
+                            send_engine_progress_update(
+                                engine.clone(),
+                                EngineProgressUpdate::Error(e.to_string()),
+                            );
+                            return (engine, Err(e));
                         }
                     };
 
-                    send_engine_progress_update(engine, EngineProgressUpdate::Done);
+                    send_engine_progress_update(engine.clone(), EngineProgressUpdate::Done);
 
                     response
                 }
                 RequestResponse::Instant(_) => {
-                    error!("unexpected instant response for image request");
-                    EngineImagesResponse::new()
+                    error!("unexpected instant response for {} request", R::KIND);
+                    R::default()
                 }
-                RequestResponse::None => EngineImagesResponse::new(),
+                RequestResponse::None => R::default(),
             };
 
-            Ok((engine, response))
-        });
-    }
-
-    let mut response_futures = Vec::new();
-    for request in requests {
-        response_futures.push(request);
+            (engine, Ok(response))
+        }));
     }
 
-    let responses_result: eyre::Result<HashMap<_, _>> =
-        join_all(response_futures).await.into_iter().collect();
-    let responses = responses_result?;
+    let request_count = requests.len();
+    let responses =
+        join_engine_requests(requests, query, start_time, send_engine_progress_update).await;
+    // don't cache the results if an engine failed or timed out
+    let is_complete = responses.len() == request_count;
 
-    let response = ranking::merge_images_responses(query.config.clone(), responses);
+    let response = R::merge(query, responses);
     progress_tx.send(ProgressUpdate::new(
-        ProgressUpdateData::Response(ResponseForTab::Images(response.clone())),
+        ProgressUpdateData::Response(Box::new(response.clone())),
         start_time,
     ))?;
 
+    if is_complete {
+        cache::insert(
+            query,
+            cache::CachedSearch {
+                response,
+                infobox: None,
+            },
+        );
+    }
+
     Ok(())
 }
 
@@ -547,20 +1116,65 @@ pub async fn search(
     info!("Doing search");
 
     let progress_tx = &progress_tx;
+    let request_timer = metrics::RequestTimer::default();
     let send_engine_progress_update = |engine: Engine, update: EngineProgressUpdate| {
-        let _ = progress_tx.send(ProgressUpdate::new(
-            ProgressUpdateData::Engine { engine, update },
-            start_time,
-        ));
+        let progress_update =
+            ProgressUpdate::new(ProgressUpdateData::Engine { engine, update }, start_time);
+        if let ProgressUpdateData::Engine { engine, update } = &progress_update.data {
+            request_timer.observe(engine, update, progress_update.time_ms);
+        }
+        let _ = progress_tx.send(progress_update);
     };
 
+    if let Some(cached) = cache::get(query) {
+        info!("Using cached results");
+        progress_tx.send(ProgressUpdate::new(
+            ProgressUpdateData::CacheHit,
+            start_time,
+        ))?;
+        progress_tx.send(ProgressUpdate::new(
+            ProgressUpdateData::Response(Box::new(cached.response)),
+            start_time,
+        ))?;
+        if let Some(infobox) = cached.infobox {
+            progress_tx.send(ProgressUpdate::new(
+                ProgressUpdateData::PostSearchInfobox(infobox),
+                start_time,
+            ))?;
+        }
+        return Ok(());
+    }
+
     match query.tab {
         SearchTab::All => {
             make_requests(query, progress_tx, start_time, &send_engine_progress_update).await?
         }
         SearchTab::Images if query.config.image_search.enabled => {
-            make_image_requests(query, progress_tx, start_time, &send_engine_progress_update)
-                .await?
+            make_tab_requests::<EngineImagesResponse>(
+                query,
+                progress_tx,
+                start_time,
+                &send_engine_progress_update,
+            )
+            .await?
+        }
+        SearchTab::News => {
+            make_tab_requests::<EngineNewsResponse>(
+                query,
+                progress_tx,
+                start_time,
+                &send_engine_progress_update,
+            )
+            .await?
+        }
+        SearchTab::Videos => {
+            make_tab_requests::<EngineVideosResponse>(
+                query,
+                progress_tx,
+                start_time,
+                &send_engine_progress_update,
+            )
+            .await?
         }
         _ => {
             bail!("unknown tab");
@@ -571,18 +1185,19 @@ pub async fn search(
 }
 
 pub async fn autocomplete(config: &Config, query: &str) -> eyre::Result<Vec<String>> {
+    let start_time = Instant::now();
     let mut requests = Vec::new();
-    for &engine in Engine::all() {
-        let config = config.engines.get(engine);
-        if !config.enabled {
+    for engine in Engine::all().iter().cloned() {
+        let engine_config = config.engines.get(&engine);
+        if !engine_config.enabled {
             continue;
         }
 
-        if let Some(request) = engine.request_autocomplete(query) {
+        if let Some(request) = engine.request_autocomplete(query, config) {
             requests.push(async move {
                 let response = match request {
                     RequestAutocompleteResponse::Http(request) => {
-                        let res = request.send().await?;
+                        let res = send_engine_request(*request, &engine, config).await?;
                         let body = res.text().await?;
                         engine.parse_autocomplete_response(&body)?
                     }
@@ -600,6 +1215,7 @@ pub async fn autocomplete(config: &Config, query: &str) -> eyre::Result<Vec<Stri
 
     let autocomplete_results_result: eyre::Result<HashMap<_, _>> =
         join_all(autocomplete_futures).await.into_iter().collect();
+    metrics::record_autocomplete(start_time.elapsed());
     let autocomplete_results = autocomplete_results_result?;
 
     Ok(ranking::merge_autocomplete_responses(
@@ -608,7 +1224,9 @@ pub async fn autocomplete(config: &Config, query: &str) -> eyre::Result<Vec<Stri
     ))
 }
 
-pub static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+pub static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| client_builder().build().unwrap());
+
+fn client_builder() -> reqwest::ClientBuilder {
     reqwest::ClientBuilder::new()
         .local_address(IpAddr::from_str("0.0.0.0").unwrap())
         // we pretend to be a normal browser so websites don't block us
@@ -620,9 +1238,62 @@ pub static CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
             headers
         })
         .timeout(Duration::from_secs(10))
-        .build()
-        .unwrap()
-});
+}
+
+/// Clients for every proxy that's been used, keyed by the proxy url. These are
+/// kept around so connections can be reused between searches.
+static PROXY_CLIENTS: LazyLock<Mutex<HashMap<String, reqwest::Client>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Get the client that requests to the engine should be sent with, which
+/// depends on the proxy that it's configured to use.
+fn client_for_engine(engine: &Engine, config: &Config) -> eyre::Result<reqwest::Client> {
+    let Some(proxy) = config
+        .engines
+        .get(engine)
+        .proxy
+        .as_ref()
+        .or(config.proxy.as_ref())
+        // an empty string means the engine shouldn't use the default proxy
+        .filter(|proxy| !proxy.is_empty())
+    else {
+        return Ok(CLIENT.clone());
+    };
+
+    let mut proxy_clients = PROXY_CLIENTS.lock();
+    if let Some(client) = proxy_clients.get(proxy) {
+        return Ok(client.clone());
+    }
+    let client = client_builder()
+        .proxy(reqwest::Proxy::all(proxy)?)
+        .build()?;
+    proxy_clients.insert(proxy.clone(), client.clone());
+    Ok(client)
+}
+
+/// Send a request that was made by an engine, applying the engine's timeout
+/// and proxy.
+async fn send_engine_request(
+    request: RequestBuilder,
+    engine: &Engine,
+    config: &Config,
+) -> eyre::Result<reqwest::Response> {
+    let request = with_engine_timeout(request, engine, config);
+    // engines build their requests with the default client, so we take the
+    // request out of it and send it with the client for the engine's proxy instead
+    let (_, request) = request.build_split();
+    let mut request = request?;
+    // engines can set their own Accept-Language, but most of them use the one from
+    // the config
+    if let Ok(accept_language) = HeaderValue::from_str(&config.accept_language()) {
+        request
+            .headers_mut()
+            .entry(header::ACCEPT_LANGUAGE)
+            .or_insert(accept_language);
+    }
+    let client = client_for_engine(engine, config)?;
+    Ok(client.execute(request).await?)
+}
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Response {
@@ -630,6 +1301,11 @@ pub struct Response {
     pub featured_snippet: Option<FeaturedSnippet>,
     pub answer: Option<Answer>,
     pub infobox: Option<Infobox>,
+    pub page: usize,
+    /// The engines that were chosen for the query, if any.
+    #[serde(skip_serializing_if = "EngineSelection::is_empty")]
+    pub engine_selection: EngineSelection,
+    pub engine_health: BTreeMap<Engine, health::EngineHealthStatus>,
     #[serde(skip)]
     pub config: Arc<Config>,
 }
@@ -637,6 +1313,35 @@ pub struct Response {
 #[derive(Debug, Clone, Serialize)]
 pub struct ImagesResponse {
     pub image_results: Vec<SearchResult<EngineImageResult>>,
+    pub page: usize,
+    /// The engines that were chosen for the query, if any.
+    #[serde(skip_serializing_if = "EngineSelection::is_empty")]
+    pub engine_selection: EngineSelection,
+    pub engine_health: BTreeMap<Engine, health::EngineHealthStatus>,
+    #[serde(skip)]
+    pub config: Arc<Config>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NewsResponse {
+    pub news_results: Vec<SearchResult<EngineNewsResult>>,
+    pub page: usize,
+    /// The engines that were chosen for the query, if any.
+    #[serde(skip_serializing_if = "EngineSelection::is_empty")]
+    pub engine_selection: EngineSelection,
+    pub engine_health: BTreeMap<Engine, health::EngineHealthStatus>,
+    #[serde(skip)]
+    pub config: Arc<Config>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VideosResponse {
+    pub video_results: Vec<SearchResult<EngineVideoResult>>,
+    pub page: usize,
+    /// The engines that were chosen for the query, if any.
+    #[serde(skip_serializing_if = "EngineSelection::is_empty")]
+    pub engine_selection: EngineSelection,
+    pub engine_health: BTreeMap<Engine, health::EngineHealthStatus>,
     #[serde(skip)]
     pub config: Arc<Config>,
 }
@@ -646,6 +1351,33 @@ pub struct ImagesResponse {
 pub enum ResponseForTab {
     All(Response),
     Images(ImagesResponse),
+    News(NewsResponse),
+    Videos(VideosResponse),
+}
+
+impl ResponseForTab {
+    #[must_use]
+    pub fn page(&self) -> usize {
+        match self {
+            ResponseForTab::All(r) => r.page,
+            ResponseForTab::Images(r) => r.page,
+            ResponseForTab::News(r) => r.page,
+            ResponseForTab::Videos(r) => r.page,
+        }
+    }
+
+    /// Whether there might be more results on the next page, which is assumed
+    /// as long as this page had any.
+    #[must_use]
+    pub fn has_next_page(&self) -> bool {
+        let has_results = match self {
+            ResponseForTab::All(r) => !r.search_results.is_empty(),
+            ResponseForTab::Images(r) => !r.image_results.is_empty(),
+            ResponseForTab::News(r) => !r.news_results.is_empty(),
+            ResponseForTab::Videos(r) => !r.video_results.is_empty(),
+        };
+        has_results && self.page() < MAX_PAGE
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -688,3 +1420,24 @@ where
 {
     serializer.serialize_str(&markup.0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_engine_selection_without_matching_engines() {
+        let config = Config::default();
+
+        // google_scholar is disabled by default
+        let selection = EngineSelection::parse("google_scholar,Gogle", &config);
+        assert_eq!(selection.only, Some(vec![]));
+        assert_eq!(selection.unmatched, ["google_scholar", "gogle"]);
+        assert!(!selection.is_enabled(&Engine::Google, true));
+        assert_eq!(selection.to_string(), "google_scholar,gogle");
+
+        let selection = EngineSelection::parse("-gogle", &config);
+        assert!(selection.is_empty());
+        assert!(selection.is_enabled(&Engine::Google, true));
+    }
+}
@@ -0,0 +1,324 @@
+//! Search operators like `site:`, `filetype:`, quoted phrases, and `-term`.
+//!
+//! The query is parsed once, and then every engine gets a version of it with
+//! only the operators that it supports natively. Operators that an engine
+//! doesn't support are removed from its query and enforced on the merged
+//! results instead.
+
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    /// `"exact phrase"`
+    Phrase,
+    /// `-term`
+    Exclude,
+    /// `site:example.com`
+    Site,
+    /// `-site:example.com`
+    ExcludeSite,
+    /// `filetype:pdf`
+    Filetype,
+}
+
+impl Operator {
+    pub const ALL: &'static [Operator] = &[
+        Operator::Phrase,
+        Operator::Exclude,
+        Operator::Site,
+        Operator::ExcludeSite,
+        Operator::Filetype,
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryTerm {
+    Word(String),
+    Phrase(String),
+    /// A word or phrase that shouldn't be in the results.
+    Exclude(String),
+    Site(String),
+    ExcludeSite(String),
+    Filetype(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedQuery {
+    pub terms: Vec<QueryTerm>,
+}
+
+impl ParsedQuery {
+    #[must_use]
+    pub fn parse(query: &str) -> Self {
+        let mut terms = Vec::new();
+        let mut chars = query.chars().peekable();
+
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            let Some(&c) = chars.peek() else {
+                break;
+            };
+
+            let negated = c == '-';
+            if negated {
+                chars.next();
+            }
+
+            if chars.next_if_eq(&'"').is_some() {
+                // an unclosed quote makes the rest of the query a phrase
+                let phrase = chars.by_ref().take_while(|&c| c != '"').collect::<String>();
+                let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+                if phrase.is_empty() {
+                    continue;
+                }
+                terms.push(if negated {
+                    QueryTerm::Exclude(phrase)
+                } else {
+                    QueryTerm::Phrase(phrase)
+                });
+                continue;
+            }
+
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                word.push(c);
+            }
+
+            terms.push(match parse_operator(&word) {
+                Some(("site", value)) if negated => QueryTerm::ExcludeSite(clean_site(value)),
+                Some(("site", value)) => QueryTerm::Site(clean_site(value)),
+                Some(("filetype", value)) if !negated => {
+                    QueryTerm::Filetype(value.trim_start_matches('.').to_lowercase())
+                }
+                _ if negated && !word.is_empty() => QueryTerm::Exclude(word),
+                // a lone - or an unknown operator is kept as it is
+                _ if negated => QueryTerm::Word(format!("-{word}")),
+                _ => QueryTerm::Word(word),
+            });
+        }
+
+        Self { terms }
+    }
+
+    /// The query that should be sent to an engine that supports the given
+    /// operators. Phrases are turned into normal words and the other
+    /// unsupported operators are removed.
+    #[must_use]
+    pub fn to_engine_query(&self, supported: &[Operator]) -> String {
+        self.terms
+            .iter()
+            .filter_map(|term| match term {
+                QueryTerm::Word(word) => Some(word.clone()),
+                QueryTerm::Phrase(phrase) if supported.contains(&Operator::Phrase) => {
+                    Some(format!("\"{phrase}\""))
+                }
+                QueryTerm::Phrase(phrase) => Some(phrase.clone()),
+                QueryTerm::Exclude(text) if supported.contains(&Operator::Exclude) => {
+                    if text.contains(' ') {
+                        Some(format!("-\"{text}\""))
+                    } else {
+                        Some(format!("-{text}"))
+                    }
+                }
+                QueryTerm::Site(site) if supported.contains(&Operator::Site) => {
+                    Some(format!("site:{site}"))
+                }
+                QueryTerm::ExcludeSite(site) if supported.contains(&Operator::ExcludeSite) => {
+                    Some(format!("-site:{site}"))
+                }
+                QueryTerm::Filetype(filetype) if supported.contains(&Operator::Filetype) => {
+                    Some(format!("filetype:{filetype}"))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Only the words and phrases in the query, without any operators.
+    #[must_use]
+    pub fn words(&self) -> String {
+        self.to_engine_query(&[])
+    }
+
+    /// Check whether a result from an engine that supports the given
+    /// operators satisfies the other operators in the query. Phrases aren't
+    /// checked since the title and description are only a small part of the
+    /// page.
+    #[must_use]
+    pub fn matches(
+        &self,
+        supported: &[Operator],
+        url: &str,
+        title: &str,
+        description: &str,
+    ) -> bool {
+        let path = Url::parse(url)
+            .map(|u| u.path().to_string())
+            .unwrap_or_default();
+
+        self.matches_site(supported, url)
+            && self.terms.iter().all(|term| match term {
+                QueryTerm::Exclude(text) if !supported.contains(&Operator::Exclude) => {
+                    !contains_words(title, text) && !contains_words(description, text)
+                }
+                QueryTerm::Filetype(filetype) if !supported.contains(&Operator::Filetype) => path
+                    .rsplit_once('.')
+                    .is_some_and(|(_, ext)| ext.eq_ignore_ascii_case(filetype)),
+                _ => true,
+            })
+    }
+
+    /// Check only the `site:` and `-site:` operators that the engine doesn't
+    /// support, for results like images where the other operators don't make
+    /// sense.
+    #[must_use]
+    pub fn matches_site(&self, supported: &[Operator], url: &str) -> bool {
+        let parsed_url = Url::parse(url).ok();
+        let host = parsed_url
+            .as_ref()
+            .and_then(|u| u.host_str())
+            .unwrap_or_default()
+            .trim_start_matches("www.");
+        let path = parsed_url.as_ref().map(|u| u.path()).unwrap_or_default();
+
+        self.terms.iter().all(|term| match term {
+            QueryTerm::Site(site) if !supported.contains(&Operator::Site) => {
+                site_matches(site, host, path)
+            }
+            QueryTerm::ExcludeSite(site) if !supported.contains(&Operator::ExcludeSite) => {
+                !site_matches(site, host, path)
+            }
+            _ => true,
+        })
+    }
+}
+
+/// Whether the text contains the words, ignoring case. The words have to be
+/// whole, so `go` isn't in "Google".
+fn contains_words(text: &str, words: &str) -> bool {
+    let text = text.to_lowercase();
+    let words = words.to_lowercase();
+    text.match_indices(&words).any(|(i, _)| {
+        let before = text[..i].chars().next_back();
+        let after = text[i + words.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// Split a word like `site:example.com` into the operator and its value.
+fn parse_operator(word: &str) -> Option<(&str, &str)> {
+    let (operator, value) = word.split_once(':')?;
+    if value.is_empty() {
+        return None;
+    }
+    let operator = match operator.to_lowercase().as_str() {
+        "site" => "site",
+        "filetype" | "ext" => "filetype",
+        _ => return None,
+    };
+    Some((operator, value))
+}
+
+fn clean_site(site: &str) -> String {
+    let site = site
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_start_matches("www.")
+        .trim_end_matches('/');
+    site.to_lowercase()
+}
+
+/// Whether the host and path are on the site, which can be a domain (which
+/// includes its subdomains) optionally followed by a path.
+fn site_matches(site: &str, host: &str, path: &str) -> bool {
+    let (site_host, site_path) = match site.split_once('/') {
+        Some((site_host, site_path)) => (site_host, site_path),
+        None => (site, ""),
+    };
+    let host_matches = host.eq_ignore_ascii_case(site_host)
+        || host
+            .to_lowercase()
+            .ends_with(&format!(".{}", site_host.to_lowercase()));
+    // the path has to end where the site's path ends, so `example.com/a` doesn't
+    // include `example.com/ab`
+    let path_matches = site_path.is_empty()
+        || path
+            .trim_start_matches('/')
+            .strip_prefix(site_path)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'));
+    host_matches && path_matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            ParsedQuery::parse(r#"rust "borrow checker" -site:reddit.com site:docs.rs -unsafe filetype:.PDF -"hello world" -"#).terms,
+            vec![
+                QueryTerm::Word("rust".to_string()),
+                QueryTerm::Phrase("borrow checker".to_string()),
+                QueryTerm::ExcludeSite("reddit.com".to_string()),
+                QueryTerm::Site("docs.rs".to_string()),
+                QueryTerm::Exclude("unsafe".to_string()),
+                QueryTerm::Filetype("pdf".to_string()),
+                QueryTerm::Exclude("hello world".to_string()),
+                QueryTerm::Word("-".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_engine_query() {
+        let query = ParsedQuery::parse(r#"rust "borrow checker" -site:reddit.com -unsafe"#);
+        assert_eq!(
+            query.to_engine_query(Operator::ALL),
+            r#"rust "borrow checker" -site:reddit.com -unsafe"#
+        );
+        assert_eq!(
+            query.to_engine_query(&[Operator::Exclude]),
+            "rust borrow checker -unsafe"
+        );
+        assert_eq!(query.words(), "rust borrow checker");
+    }
+
+    #[test]
+    fn test_matches() {
+        let query = ParsedQuery::parse("rust site:docs.rs -site:docs.rs/tokio -unsafe");
+        assert!(query.matches(&[], "https://docs.rs/serde", "serde", "serialization"));
+        assert!(query.matches(&[], "https://www.docs.rs/serde", "serde", ""));
+        assert!(!query.matches(&[], "https://docs.rs/tokio/latest", "tokio", ""));
+        assert!(!query.matches(&[], "https://docs.rs/tokio", "tokio", ""));
+        assert!(query.matches(&[], "https://docs.rs/tokio-util", "tokio-util", ""));
+        assert!(!query.matches(&[], "https://crates.io/serde", "serde", ""));
+        assert!(!query.matches(&[], "https://docs.rs/bytes", "bytes", "Unsafe code"));
+        assert!(query.matches(&[], "https://docs.rs/bytes", "bytes", "no unsafety"));
+
+        let query = ParsedQuery::parse("manual filetype:pdf");
+        assert!(query.matches(&[], "https://example.com/manual.PDF", "", ""));
+        assert!(!query.matches(&[], "https://example.com/manual.html", "", ""));
+    }
+
+    #[test]
+    fn test_matches_whole_words() {
+        let query = ParsedQuery::parse(r#"search -go -"hello world""#);
+        assert!(query.matches(&[], "https://google.com", "Google Search", ""));
+        assert!(!query.matches(&[], "https://go.dev", "The Go Programming Language", ""));
+        assert!(!query.matches(&[], "https://go.dev", "go", ""));
+        assert!(query.matches(&[], "https://example.com", "", "hello worldwide"));
+        assert!(!query.matches(&[], "https://example.com", "", "Hello, world? Hello world!"));
+    }
+
+    #[test]
+    fn test_matches_supported_operators() {
+        // engines that support an operator already applied it, so their results
+        // aren't filtered again
+        let query = ParsedQuery::parse("rust site:docs.rs -unsafe");
+        assert!(!query.matches(&[], "https://crates.io", "unsafe", ""));
+        assert!(!query.matches(&[Operator::Site], "https://crates.io", "unsafe", ""));
+        assert!(query.matches(Operator::ALL, "https://crates.io", "unsafe", ""));
+    }
+}
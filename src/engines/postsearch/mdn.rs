@@ -1,15 +1,22 @@
 use maud::{html, PreEscaped};
 use scraper::{Html, Selector};
-use serde::Deserialize;
-use tracing::error;
+use serde::{Deserialize, Serialize};
 
-use crate::engines::{Engine, HttpResponse, Response, CLIENT};
+use crate::engines::{HttpResponse, Response, CLIENT};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
 pub struct MdnConfig {
+    /// How many sections of the page are shown, or 0 to show all of them.
     pub max_sections: usize,
 }
 
+impl Default for MdnConfig {
+    fn default() -> Self {
+        Self { max_sections: 1 }
+    }
+}
+
 pub fn request(response: &Response) -> Option<reqwest::RequestBuilder> {
     for search_result in response.search_results.iter().take(8) {
         if search_result
@@ -27,14 +34,7 @@ pub fn request(response: &Response) -> Option<reqwest::RequestBuilder> {
 pub fn parse_response(
     HttpResponse { res, body, config }: &HttpResponse,
 ) -> Option<PreEscaped<String>> {
-    let config_toml = config.engines.get(Engine::Mdn).extra.clone();
-    let config: MdnConfig = match toml::Value::Table(config_toml).try_into() {
-        Ok(args) => args,
-        Err(err) => {
-            error!("Failed to parse Mdn config: {err}");
-            return None;
-        }
-    };
+    let config = MdnConfig::get(config);
 
     let url = res.url().clone();
 
@@ -1,4 +1,6 @@
-use std::{collections::HashMap, sync::Arc};
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
 
 use crate::{
     config::Config,
@@ -6,21 +8,25 @@ use crate::{
 };
 
 use super::{
-    Answer, AutocompleteResult, Engine, EngineImageResult, EngineImagesResponse, EngineResponse,
-    EngineSearchResult, FeaturedSnippet, ImagesResponse, Infobox, Response, SearchResult,
+    health, Answer, AutocompleteResult, Engine, EngineImageResult, EngineImagesResponse,
+    EngineNewsResponse, EngineNewsResult, EngineResponse, EngineSearchResult, EngineVideoResult,
+    EngineVideosResponse, FeaturedSnippet, ImagesResponse, Infobox, NewsResponse, Response,
+    SearchQuery, SearchResult, VideosResponse,
 };
 
 pub fn merge_engine_responses(
-    config: Arc<Config>,
+    query: &SearchQuery,
     responses: HashMap<Engine, EngineResponse>,
 ) -> Response {
+    let config = query.config.clone();
+    let page = query.page;
     let mut search_results: Vec<SearchResult<EngineSearchResult>> = Vec::new();
     let mut featured_snippet: Option<FeaturedSnippet> = None;
     let mut answer: Option<Answer> = None;
     let mut infobox: Option<Infobox> = None;
 
     for (engine, response) in responses {
-        let engine_config = config.engines.get(engine);
+        let engine_config = config.engines.get(&engine);
 
         for (result_index, mut search_result) in response.search_results.into_iter().enumerate() {
             // position 1 has a score of 1, position 2 has a score of 0.5, position 3 has a
@@ -36,6 +42,16 @@ pub fn merge_engine_responses(
             }
             let result_score = result_score * url_weight;
 
+            // enforce the operators that some engines don't support natively
+            if !query.parsed.matches(
+                engine.supported_operators(),
+                &search_result.url,
+                &search_result.title,
+                &search_result.description,
+            ) {
+                continue;
+            }
+
             if let Some(existing_result) = search_results
                 .iter_mut()
                 .find(|r| r.result.url == search_result.url)
@@ -46,7 +62,7 @@ pub fn merge_engine_responses(
                     > existing_result
                         .engines
                         .iter()
-                        .map(|&other_engine| {
+                        .map(|other_engine| {
                             let other_engine_config = config.engines.get(other_engine);
                             other_engine_config.weight
                         })
@@ -57,12 +73,12 @@ pub fn merge_engine_responses(
                     existing_result.result.description = search_result.description;
                 }
 
-                existing_result.engines.insert(engine);
+                existing_result.engines.insert(engine.clone());
                 existing_result.score += result_score;
             } else {
                 search_results.push(SearchResult {
                     result: search_result,
-                    engines: [engine].iter().copied().collect(),
+                    engines: [engine.clone()].into(),
                     score: result_score,
                 });
             }
@@ -71,7 +87,7 @@ pub fn merge_engine_responses(
         if let Some(mut engine_featured_snippet) = response.featured_snippet {
             // if it has a higher weight than the current featured snippet
             let featured_snippet_weight = featured_snippet.as_ref().map_or(0., |s| {
-                let other_engine_config = config.engines.get(s.engine);
+                let other_engine_config = config.engines.get(&s.engine);
                 other_engine_config.weight
             });
 
@@ -89,7 +105,7 @@ pub fn merge_engine_responses(
                     url: engine_featured_snippet.url,
                     title: engine_featured_snippet.title,
                     description: engine_featured_snippet.description,
-                    engine,
+                    engine: engine.clone(),
                 });
             }
         }
@@ -97,13 +113,13 @@ pub fn merge_engine_responses(
         if let Some(engine_answer_html) = response.answer_html {
             // if it has a higher weight than the current answer
             let answer_weight = answer.as_ref().map_or(0., |s| {
-                let other_engine_config = config.engines.get(s.engine);
+                let other_engine_config = config.engines.get(&s.engine);
                 other_engine_config.weight
             });
             if engine_config.weight > answer_weight {
                 answer = Some(Answer {
                     html: engine_answer_html,
-                    engine,
+                    engine: engine.clone(),
                 });
             }
         }
@@ -111,7 +127,7 @@ pub fn merge_engine_responses(
         if let Some(engine_infobox_html) = response.infobox_html {
             // if it has a higher weight than the current infobox
             let infobox_weight = infobox.as_ref().map_or(0., |s| {
-                let other_engine_config = config.engines.get(s.engine);
+                let other_engine_config = config.engines.get(&s.engine);
                 other_engine_config.weight
             });
             if engine_config.weight > infobox_weight {
@@ -125,11 +141,21 @@ pub fn merge_engine_responses(
 
     search_results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
 
+    if page > 1 {
+        // answers, infoboxes, and featured snippets only belong on the first page
+        featured_snippet = None;
+        answer = None;
+        infobox = None;
+    }
+
     Response {
         search_results,
         featured_snippet,
         answer,
         infobox,
+        page,
+        engine_selection: query.engine_selection.clone(),
+        engine_health: health::statuses(&config),
         config,
     }
 }
@@ -141,7 +167,7 @@ pub fn merge_autocomplete_responses(
     let mut autocomplete_results: Vec<AutocompleteResult> = Vec::new();
 
     for (engine, response) in responses {
-        let engine_config = config.engines.get(engine);
+        let engine_config = config.engines.get(&engine);
 
         for (result_index, autocomplete_result) in response.into_iter().enumerate() {
             // position 1 has a score of 1, position 2 has a score of 0.5, position 3 has a
@@ -169,13 +195,15 @@ pub fn merge_autocomplete_responses(
 }
 
 pub fn merge_images_responses(
-    config: Arc<Config>,
+    query: &SearchQuery,
     responses: HashMap<Engine, EngineImagesResponse>,
 ) -> ImagesResponse {
+    let config = query.config.clone();
+    let page = query.page;
     let mut image_results: Vec<SearchResult<EngineImageResult>> = Vec::new();
 
     for (engine, response) in responses {
-        let engine_config = config.engines.get(engine);
+        let engine_config = config.engines.get(&engine);
 
         for (result_index, image_result) in response.image_results.into_iter().enumerate() {
             // position 1 has a score of 1, position 2 has a score of 0.5, position 3 has a
@@ -183,6 +211,13 @@ pub fn merge_images_responses(
             let base_result_score = 1. / (result_index + 1) as f64;
             let result_score = base_result_score * engine_config.weight;
 
+            if !query
+                .parsed
+                .matches_site(engine.supported_operators(), &image_result.page_url)
+            {
+                continue;
+            }
+
             if let Some(existing_result) = image_results
                 .iter_mut()
                 .find(|r| r.result.image_url == image_result.image_url)
@@ -193,7 +228,7 @@ pub fn merge_images_responses(
                     > existing_result
                         .engines
                         .iter()
-                        .map(|&other_engine| {
+                        .map(|other_engine| {
                             let other_engine_config = config.engines.get(other_engine);
                             other_engine_config.weight
                         })
@@ -204,12 +239,12 @@ pub fn merge_images_responses(
                     existing_result.result.page_url = image_result.page_url;
                 }
 
-                existing_result.engines.insert(engine);
+                existing_result.engines.insert(engine.clone());
                 existing_result.score += result_score;
             } else {
                 image_results.push(SearchResult {
                     result: image_result,
-                    engines: [engine].iter().copied().collect(),
+                    engines: [engine.clone()].into(),
                     score: result_score,
                 });
             }
@@ -220,6 +255,214 @@ pub fn merge_images_responses(
 
     ImagesResponse {
         image_results,
+        page,
+        engine_selection: query.engine_selection.clone(),
+        engine_health: health::statuses(&config),
         config,
     }
 }
+
+pub fn merge_news_responses(
+    query: &SearchQuery,
+    responses: HashMap<Engine, EngineNewsResponse>,
+) -> NewsResponse {
+    let config = query.config.clone();
+    let page = query.page;
+    let now = Utc::now();
+    let mut news_results: Vec<SearchResult<EngineNewsResult>> = Vec::new();
+
+    for (engine, response) in responses {
+        let engine_config = config.engines.get(&engine);
+
+        for (result_index, mut news_result) in response.news_results.into_iter().enumerate() {
+            // position 1 has a score of 1, position 2 has a score of 0.5, position 3 has a
+            // score of 0.33, etc.
+            let base_result_score = 1. / (result_index + 1) as f64;
+            let result_score = base_result_score * engine_config.weight;
+
+            news_result.url = apply_url_replacements(&news_result.url, &config.urls);
+            let url_weight = get_url_weight(&news_result.url, &config.urls);
+            if url_weight <= 0. {
+                continue;
+            }
+            let result_score = result_score * url_weight;
+
+            if !query.parsed.matches(
+                engine.supported_operators(),
+                &news_result.url,
+                &news_result.title,
+                &news_result.description,
+            ) {
+                continue;
+            }
+
+            // not every engine supports every time range, so we also check it here
+            if let (Some(time_range), Some(published)) = (query.time_range, news_result.published) {
+                if now - published > time_range.duration() {
+                    continue;
+                }
+            }
+
+            if let Some(existing_result) = news_results
+                .iter_mut()
+                .find(|r| r.result.url == news_result.url)
+            {
+                // if the weight of this engine is higher than every other one then replace the
+                // title and description
+                if engine_config.weight
+                    > existing_result
+                        .engines
+                        .iter()
+                        .map(|other_engine| {
+                            let other_engine_config = config.engines.get(other_engine);
+                            other_engine_config.weight
+                        })
+                        .max_by(|a, b| a.partial_cmp(b).unwrap())
+                        .unwrap_or(0.)
+                {
+                    existing_result.result.title = news_result.title;
+                    existing_result.result.description = news_result.description;
+                }
+                // engines don't always know when an article was published or have a thumbnail
+                // for it
+                if existing_result.result.published.is_none() {
+                    existing_result.result.published = news_result.published;
+                }
+                if existing_result.result.thumbnail.is_none() {
+                    existing_result.result.thumbnail = news_result.thumbnail;
+                }
+
+                existing_result.engines.insert(engine.clone());
+                existing_result.score += result_score;
+            } else {
+                news_results.push(SearchResult {
+                    result: news_result,
+                    engines: [engine.clone()].into(),
+                    score: result_score,
+                });
+            }
+        }
+    }
+
+    // newer articles are ranked higher, which is done after merging so every
+    // engine's score for the result gets the same boost
+    for news_result in &mut news_results {
+        news_result.score *= recency_weight(news_result.result.published, now);
+    }
+
+    news_results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+
+    NewsResponse {
+        news_results,
+        page,
+        engine_selection: query.engine_selection.clone(),
+        engine_health: health::statuses(&config),
+        config,
+    }
+}
+
+pub fn merge_videos_responses(
+    query: &SearchQuery,
+    responses: HashMap<Engine, EngineVideosResponse>,
+) -> VideosResponse {
+    let config = query.config.clone();
+    let page = query.page;
+    let now = Utc::now();
+    let mut video_results: Vec<SearchResult<EngineVideoResult>> = Vec::new();
+
+    for (engine, response) in responses {
+        let engine_config = config.engines.get(&engine);
+
+        for (result_index, mut video_result) in response.video_results.into_iter().enumerate() {
+            // position 1 has a score of 1, position 2 has a score of 0.5, position 3 has a
+            // score of 0.33, etc.
+            let base_result_score = 1. / (result_index + 1) as f64;
+            let result_score = base_result_score * engine_config.weight;
+
+            video_result.url = apply_url_replacements(&video_result.url, &config.urls);
+            let url_weight = get_url_weight(&video_result.url, &config.urls);
+            if url_weight <= 0. {
+                continue;
+            }
+            let result_score = result_score * url_weight;
+
+            if !query.parsed.matches(
+                engine.supported_operators(),
+                &video_result.url,
+                &video_result.title,
+                &video_result.description,
+            ) {
+                continue;
+            }
+
+            if let (Some(time_range), Some(published)) = (query.time_range, video_result.published)
+            {
+                if now - published > time_range.duration() {
+                    continue;
+                }
+            }
+
+            if let Some(existing_result) = video_results
+                .iter_mut()
+                .find(|r| r.result.url == video_result.url)
+            {
+                // if the weight of this engine is higher than every other one then replace the
+                // title and description
+                if engine_config.weight
+                    > existing_result
+                        .engines
+                        .iter()
+                        .map(|other_engine| {
+                            let other_engine_config = config.engines.get(other_engine);
+                            other_engine_config.weight
+                        })
+                        .max_by(|a, b| a.partial_cmp(b).unwrap())
+                        .unwrap_or(0.)
+                {
+                    existing_result.result.title = video_result.title;
+                    existing_result.result.description = video_result.description;
+                }
+                let existing = &mut existing_result.result;
+                if existing.channel.is_empty() {
+                    existing.channel = video_result.channel;
+                }
+                existing.duration = existing.duration.or(video_result.duration);
+                existing.published = existing.published.or(video_result.published);
+                if existing.thumbnail.is_none() {
+                    existing.thumbnail = video_result.thumbnail;
+                }
+
+                existing_result.engines.insert(engine.clone());
+                existing_result.score += result_score;
+            } else {
+                video_results.push(SearchResult {
+                    result: video_result,
+                    engines: [engine.clone()].into(),
+                    score: result_score,
+                });
+            }
+        }
+    }
+
+    video_results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+
+    VideosResponse {
+        video_results,
+        page,
+        engine_selection: query.engine_selection.clone(),
+        engine_health: health::statuses(&config),
+        config,
+    }
+}
+
+/// A multiplier for the score of a news result based on how long ago it was
+/// published. Articles from right now have a weight of 2, articles from a day
+/// ago have a weight of 1.5, and it approaches 1 as they get older. Results
+/// without a date are treated as if they're a week old.
+fn recency_weight(published: Option<DateTime<Utc>>, now: DateTime<Utc>) -> f64 {
+    let age_days = match published {
+        Some(published) => (now - published).num_minutes().max(0) as f64 / (60. * 24.),
+        None => 7.,
+    };
+    1. + 1. / (1. + age_days)
+}
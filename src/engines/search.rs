@@ -2,6 +2,7 @@ pub mod bing;
 pub mod brave;
 pub mod google;
 pub mod google_scholar;
+pub mod invidious;
 pub mod marginalia;
 pub mod rightdao;
 pub mod stract;
@@ -1,20 +1,55 @@
-use base64::Engine;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::Engine as _;
 use eyre::eyre;
 use scraper::{ElementRef, Html, Selector};
 use tracing::warn;
 use url::Url;
 
 use crate::{
-    engines::{EngineImageResult, EngineImagesResponse, EngineResponse, CLIENT},
-    parse::{parse_html_response_with_opts, ParseOpts, QueryMethod},
+    engines::{
+        Engine, EngineImageResult, EngineImagesResponse, EngineNewsResponse, EngineNewsResult,
+        EngineResponse, EngineVideoResult, EngineVideosResponse, SearchQuery, TimeRange, CLIENT,
+    },
+    parse::{
+        parse_duration, parse_html_response_with_opts, parse_published, ParseOpts, QueryMethod,
+    },
 };
 
-pub fn request(query: &str) -> reqwest::RequestBuilder {
+pub fn request(query: &SearchQuery) -> reqwest::RequestBuilder {
+    // rcrse:"1" makes it not try to autocorrect
+    let mut filters = "rcrse:\"1\"".to_string();
+    if let Some(time_range) = query.time_range {
+        let ex1 = match time_range {
+            TimeRange::Day => "ez1".to_string(),
+            TimeRange::Week => "ez2".to_string(),
+            TimeRange::Month => "ez3".to_string(),
+            TimeRange::Year => {
+                // bing doesn't have a preset for the past year, so we give it a range of days
+                // since the unix epoch instead
+                let today = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+                    / 86400;
+                format!("ez5_{}_{today}", today - 365)
+            }
+        };
+        filters.push_str(&format!(" ex1:\"{ex1}\""));
+    }
+
     CLIENT.get(
         Url::parse_with_params(
             "https://www.bing.com/search",
-            // filters=rcrse:"1" makes it not try to autocorrect
-            &[("q", query), ("filters", "rcrse:\"1\"")],
+            &[
+                ("q", query.engine_query(&Engine::Bing).as_str()),
+                ("filters", &filters),
+                // bing's offsets start at 1
+                ("first", &(query.offset(10) + 1).to_string()),
+                ("adlt", query.config.safe_search.as_str()),
+                ("setlang", &query.config.language),
+                ("cc", &query.config.region),
+            ],
         )
         .unwrap(),
     )
@@ -49,13 +84,14 @@ pub fn parse_response(body: &str) -> eyre::Result<EngineResponse> {
                         scraper::Node::Text(t) => {
                             description.push_str(&t.text);
                         }
-                        scraper::Node::Element(inner_el) => {
-                            if !inner_el
-                                .has_class("algoSlug_icon", scraper::CaseSensitivity::CaseSensitive)
-                            {
-                                let element_ref = ElementRef::wrap(inner_node).unwrap();
-                                description.push_str(&element_ref.text().collect::<String>());
-                            }
+                        scraper::Node::Element(inner_el)
+                            if !inner_el.has_class(
+                                "algoSlug_icon",
+                                scraper::CaseSensitivity::CaseSensitive,
+                            ) =>
+                        {
+                            let element_ref = ElementRef::wrap(inner_node).unwrap();
+                            description.push_str(&element_ref.text().collect::<String>());
                         }
                         _ => {}
                     }
@@ -66,15 +102,18 @@ pub fn parse_response(body: &str) -> eyre::Result<EngineResponse> {
     )
 }
 
-pub fn request_images(query: &str) -> reqwest::RequestBuilder {
+pub fn request_images(query: &SearchQuery) -> reqwest::RequestBuilder {
     CLIENT.get(
         Url::parse_with_params(
             "https://www.bing.com/images/async",
             &[
-                ("q", query),
+                ("q", query.engine_query(&Engine::Bing).as_str()),
                 ("async", "content"),
-                ("first", "1"),
+                ("first", &(query.offset(35) + 1).to_string()),
                 ("count", "35"),
+                ("adlt", query.config.safe_search.as_str()),
+                ("setlang", &query.config.language),
+                ("cc", &query.config.region),
             ],
         )
         .unwrap(),
@@ -148,6 +187,196 @@ pub fn parse_images_response(body: &str) -> eyre::Result<EngineImagesResponse> {
     Ok(EngineImagesResponse { image_results })
 }
 
+pub fn request_news(query: &SearchQuery) -> reqwest::RequestBuilder {
+    let mut url = Url::parse_with_params(
+        // this is what bing uses to load more results when you scroll down, and it
+        // only has the results
+        "https://www.bing.com/news/infinitescrollajax",
+        &[
+            ("q", query.engine_query(&Engine::Bing).as_str()),
+            ("InfiniteScroll", "1"),
+            ("first", &(query.offset(10) + 1).to_string()),
+            ("adlt", query.config.safe_search.as_str()),
+            ("setlang", &query.config.language),
+            ("cc", &query.config.region),
+        ],
+    )
+    .unwrap();
+    // bing news doesn't have a filter for the past year, but it doesn't matter much
+    // since the results are filtered again when they're merged
+    let interval = match query.time_range {
+        Some(TimeRange::Day) => Some("interval=\"4\""),
+        Some(TimeRange::Week) => Some("interval=\"7\""),
+        Some(TimeRange::Month) => Some("interval=\"9\""),
+        Some(TimeRange::Year) | None => None,
+    };
+    if let Some(interval) = interval {
+        url.query_pairs_mut().append_pair("qft", interval);
+    }
+    CLIENT.get(url)
+}
+
+#[tracing::instrument(skip(body))]
+pub fn parse_news_response(body: &str) -> eyre::Result<EngineNewsResponse> {
+    let dom = Html::parse_document(body);
+
+    let result_sel = Selector::parse(".news-card").unwrap();
+    let title_sel = Selector::parse("a.title").unwrap();
+    let description_sel = Selector::parse(".snippet").unwrap();
+    let published_sel = Selector::parse(".source span[aria-label]").unwrap();
+    let thumbnail_sel = Selector::parse(".image img").unwrap();
+
+    let mut news_results = Vec::new();
+    for result_el in dom.select(&result_sel) {
+        let Some(title_el) = result_el.select(&title_sel).next() else {
+            warn!("couldn't get title for bing news result");
+            continue;
+        };
+        let Some(url) = result_el
+            .value()
+            .attr("url")
+            .or_else(|| title_el.value().attr("href"))
+        else {
+            warn!("couldn't get url for bing news result");
+            continue;
+        };
+
+        let description = result_el
+            .select(&description_sel)
+            .next()
+            .map(|el| el.text().collect::<String>())
+            .unwrap_or_default();
+        let published = result_el
+            .select(&published_sel)
+            .next()
+            .and_then(|el| el.value().attr("aria-label"))
+            .and_then(parse_published);
+        // the thumbnails are lazy loaded, so the src is usually a placeholder
+        let thumbnail = result_el
+            .select(&thumbnail_sel)
+            .next()
+            .and_then(|el| {
+                el.value()
+                    .attr("data-src-hq")
+                    .or_else(|| el.value().attr("data-src"))
+                    .or_else(|| el.value().attr("src"))
+            })
+            .filter(|src| !src.starts_with("data:"))
+            .map(|src| {
+                if src.starts_with('/') {
+                    format!("https://www.bing.com{src}")
+                } else {
+                    src.to_string()
+                }
+            });
+
+        news_results.push(EngineNewsResult {
+            url: url.to_string(),
+            title: title_el.text().collect::<String>().trim().to_string(),
+            description: description.trim().to_string(),
+            source: result_el
+                .value()
+                .attr("data-author")
+                .unwrap_or_default()
+                .to_string(),
+            published,
+            thumbnail,
+        });
+    }
+
+    Ok(EngineNewsResponse { news_results })
+}
+
+pub fn request_videos(query: &SearchQuery) -> reqwest::RequestBuilder {
+    let mut url = Url::parse_with_params(
+        "https://www.bing.com/videos/asyncv2",
+        &[
+            ("q", query.engine_query(&Engine::Bing).as_str()),
+            ("async", "content"),
+            ("first", &(query.offset(35) + 1).to_string()),
+            ("count", "35"),
+            ("adlt", query.config.safe_search.as_str()),
+            ("setlang", &query.config.language),
+            ("cc", &query.config.region),
+        ],
+    )
+    .unwrap();
+    if let Some(time_range) = query.time_range {
+        // the max age of the videos in minutes
+        let minutes = time_range.duration().num_minutes();
+        url.query_pairs_mut()
+            .append_pair("qft", &format!("+filterui:videoage-lt{minutes}"));
+    }
+    CLIENT.get(url)
+}
+
+#[tracing::instrument(skip(body))]
+pub fn parse_videos_response(body: &str) -> eyre::Result<EngineVideosResponse> {
+    let dom = Html::parse_document(body);
+
+    let result_sel = Selector::parse(".dg_u .mc_vtvc").unwrap();
+    let data_sel = Selector::parse(".vrhdata").unwrap();
+    let channel_sel = Selector::parse(".mc_vtvc_meta_row_channel").unwrap();
+    let meta_row_sel = Selector::parse(".mc_vtvc_meta_row span").unwrap();
+    let thumbnail_sel = Selector::parse(".mc_vtvc_th img").unwrap();
+
+    let mut video_results = Vec::new();
+    for result_el in dom.select(&result_sel) {
+        // the title, url, and duration are in the "vrhm" attribute as json
+        let Some(data) = result_el
+            .select(&data_sel)
+            .next()
+            .and_then(|el| el.value().attr("vrhm"))
+        else {
+            warn!("couldn't get data for bing video result");
+            continue;
+        };
+        let data = serde_json::from_str::<serde_json::Value>(data)?;
+        let data_str = |key: &str| {
+            data.get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let url = data_str("murl");
+        if url.is_empty() {
+            warn!("couldn't get url for bing video result");
+            continue;
+        }
+
+        // the meta row has things like the view count and how long ago it was uploaded
+        let published = result_el
+            .select(&meta_row_sel)
+            .find_map(|el| parse_published(&el.text().collect::<String>()));
+        let thumbnail = result_el
+            .select(&thumbnail_sel)
+            .next()
+            .and_then(|el| {
+                el.value()
+                    .attr("data-src-hq")
+                    .or_else(|| el.value().attr("src"))
+            })
+            .filter(|src| src.starts_with("http"))
+            .map(str::to_string);
+
+        video_results.push(EngineVideoResult {
+            url,
+            title: data_str("vt"),
+            description: String::new(),
+            channel: result_el
+                .select(&channel_sel)
+                .next()
+                .map(|el| el.text().collect::<String>().trim().to_string())
+                .unwrap_or_default(),
+            duration: parse_duration(&data_str("du")),
+            published,
+            thumbnail,
+        });
+    }
+
+    Ok(EngineVideosResponse { video_results })
+}
+
 fn clean_url(url: &str) -> eyre::Result<String> {
     // clean up bing's tracking urls
     if url.starts_with("https://www.bing.com/ck/a?") {
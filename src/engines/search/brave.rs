@@ -1,20 +1,60 @@
+use scraper::{ElementRef, Html, Selector};
+use tracing::warn;
 use url::Url;
 
 use crate::{
-    engines::{EngineResponse, RequestResponse, CLIENT},
-    parse::{parse_html_response_with_opts, ParseOpts},
+    engines::{
+        Engine, EngineNewsResponse, EngineNewsResult, EngineResponse, EngineVideoResult,
+        EngineVideosResponse, RequestResponse, SearchQuery, TimeRange, CLIENT,
+    },
+    parse::{parse_duration, parse_html_response_with_opts, parse_published, ParseOpts},
 };
 
-pub fn request(query: &str) -> RequestResponse {
-    // brave search doesn't support exact matching anymore, so disable it to not
-    // pollute the results
-    if query.chars().any(|c| c == '"') {
-        return RequestResponse::None;
+pub fn request(query: &SearchQuery) -> RequestResponse {
+    make_request("https://search.brave.com/search", query).into()
+}
+
+pub fn request_news(query: &SearchQuery) -> reqwest::RequestBuilder {
+    make_request("https://search.brave.com/news", query)
+}
+
+pub fn request_videos(query: &SearchQuery) -> reqwest::RequestBuilder {
+    make_request("https://search.brave.com/videos", query)
+}
+
+fn make_request(base_url: &str, query: &SearchQuery) -> reqwest::RequestBuilder {
+    let mut url = Url::parse_with_params(
+        base_url,
+        &[
+            ("q", query.engine_query(&Engine::Brave).as_str()),
+            // brave's offset is the page index, not the number of results
+            ("offset", &query.offset(1).to_string()),
+        ],
+    )
+    .unwrap();
+    if let Some(time_range) = query.time_range {
+        let tf = match time_range {
+            TimeRange::Day => "pd",
+            TimeRange::Week => "pw",
+            TimeRange::Month => "pm",
+            TimeRange::Year => "py",
+        };
+        url.query_pairs_mut().append_pair("tf", tf);
     }
 
     CLIENT
-        .get(Url::parse_with_params("https://search.brave.com/search", &[("q", query)]).unwrap())
-        .into()
+        .get(url)
+        // brave only lets you set safe search and the region with cookies
+        .header(
+            "Cookie",
+            format!(
+                "safesearch={}; country={}; ui_lang={}-{}",
+                query.config.safe_search.as_str(),
+                query.config.region.to_lowercase(),
+                query.config.language,
+                query.config.region.to_lowercase()
+            ),
+        )
 }
 
 pub fn parse_response(body: &str) -> eyre::Result<EngineResponse> {
@@ -27,3 +67,109 @@ pub fn parse_response(body: &str) -> eyre::Result<EngineResponse> {
             .description(".snippet-content, .video-snippet > .snippet-description"),
     )
 }
+
+pub fn parse_news_response(body: &str) -> eyre::Result<EngineNewsResponse> {
+    let dom = Html::parse_document(body);
+
+    let result_sel = Selector::parse("#results > .snippet[data-type='news']").unwrap();
+    let href_sel = Selector::parse("a[href]").unwrap();
+    let title_sel = Selector::parse(".title").unwrap();
+    let description_sel = Selector::parse(".description, .snippet-description").unwrap();
+    let source_sel =
+        Selector::parse(".site-name-content .desktop-small-semibold, .netloc").unwrap();
+    let published_sel = Selector::parse(".attr, .snippet-attributes span").unwrap();
+    let thumbnail_sel = Selector::parse(".thumbnail img[src^='http']").unwrap();
+
+    let text = |el: &ElementRef, sel: &Selector| {
+        el.select(sel)
+            .next()
+            .map(|el| el.text().collect::<String>().trim().to_string())
+    };
+
+    let mut news_results = Vec::new();
+    for result_el in dom.select(&result_sel) {
+        let Some(url) = result_el
+            .select(&href_sel)
+            .next()
+            .and_then(|el| el.value().attr("href"))
+        else {
+            warn!("couldn't get url for brave news result");
+            continue;
+        };
+        let Some(title) = text(&result_el, &title_sel) else {
+            warn!("couldn't get title for brave news result");
+            continue;
+        };
+
+        news_results.push(EngineNewsResult {
+            url: url.to_string(),
+            title,
+            description: text(&result_el, &description_sel).unwrap_or_default(),
+            source: text(&result_el, &source_sel).unwrap_or_default(),
+            // the age is in one of the attributes, along with things like the author
+            published: result_el
+                .select(&published_sel)
+                .find_map(|el| parse_published(&el.text().collect::<String>())),
+            thumbnail: result_el
+                .select(&thumbnail_sel)
+                .next()
+                .and_then(|el| el.value().attr("src"))
+                .map(str::to_string),
+        });
+    }
+
+    Ok(EngineNewsResponse { news_results })
+}
+
+pub fn parse_videos_response(body: &str) -> eyre::Result<EngineVideosResponse> {
+    let dom = Html::parse_document(body);
+
+    let result_sel = Selector::parse("#results > .snippet[data-type='videos']").unwrap();
+    let href_sel = Selector::parse("a[href]").unwrap();
+    let title_sel = Selector::parse(".title").unwrap();
+    let description_sel = Selector::parse(".description, .snippet-description").unwrap();
+    let channel_sel = Selector::parse(".channel, .author").unwrap();
+    let duration_sel = Selector::parse(".duration").unwrap();
+    let published_sel = Selector::parse(".attr, .snippet-attributes span").unwrap();
+    let thumbnail_sel = Selector::parse(".thumbnail img[src^='http']").unwrap();
+
+    let text = |el: &ElementRef, sel: &Selector| {
+        el.select(sel)
+            .next()
+            .map(|el| el.text().collect::<String>().trim().to_string())
+    };
+
+    let mut video_results = Vec::new();
+    for result_el in dom.select(&result_sel) {
+        let Some(url) = result_el
+            .select(&href_sel)
+            .next()
+            .and_then(|el| el.value().attr("href"))
+        else {
+            warn!("couldn't get url for brave video result");
+            continue;
+        };
+        let Some(title) = text(&result_el, &title_sel) else {
+            warn!("couldn't get title for brave video result");
+            continue;
+        };
+
+        video_results.push(EngineVideoResult {
+            url: url.to_string(),
+            title,
+            description: text(&result_el, &description_sel).unwrap_or_default(),
+            channel: text(&result_el, &channel_sel).unwrap_or_default(),
+            duration: text(&result_el, &duration_sel).and_then(|d| parse_duration(&d)),
+            published: result_el
+                .select(&published_sel)
+                .find_map(|el| parse_published(&el.text().collect::<String>())),
+            thumbnail: result_el
+                .select(&thumbnail_sel)
+                .next()
+                .and_then(|el| el.value().attr("src"))
+                .map(str::to_string),
+        });
+    }
+
+    Ok(EngineVideosResponse { video_results })
+}
@@ -11,30 +11,59 @@ use tracing::warn;
 use url::Url;
 
 use crate::{
-    engines::{EngineImageResult, EngineImagesResponse, EngineResponse, CLIENT},
-    parse::{parse_html_response_with_opts, ParseOpts, QueryMethod},
+    config::{Config, SafeSearch},
+    engines::{
+        Engine, EngineImageResult, EngineImagesResponse, EngineNewsResponse, EngineNewsResult,
+        EngineResponse, SearchQuery, TimeRange, CLIENT,
+    },
+    parse::{parse_html_response_with_opts, parse_published, ParseOpts, QueryMethod},
 };
 
-pub fn request(query: &str) -> reqwest::RequestBuilder {
-    let url = Url::parse_with_params(
+pub fn request(query: &SearchQuery) -> reqwest::RequestBuilder {
+    let start = query.offset(10);
+    let mut url = Url::parse_with_params(
         "https://www.google.com/search",
         &[
-            ("q", query),
+            ("q", query.engine_query(&Engine::Google).as_str()),
             // nfpr makes it not try to autocorrect
             ("nfpr", "1"),
             ("filter", "0"),
-            ("start", "0"),
+            ("start", &start.to_string()),
+            ("hl", &query.config.language),
+            ("gl", &query.config.region.to_lowercase()),
+            ("safe", safe_search_value(query.config.safe_search)),
             // mobile search, lets us easily search without js
             ("asearch", "arc"),
             // required for mobile search to work
-            ("async", &generate_async_value()),
+            ("async", &generate_async_value(start)),
         ],
     )
     .unwrap();
+    if let Some(time_range) = query.time_range {
+        url.query_pairs_mut()
+            .append_pair("tbs", time_range_value(time_range));
+    }
     CLIENT.get(url)
 }
 
-fn generate_async_value() -> String {
+fn time_range_value(time_range: TimeRange) -> &'static str {
+    match time_range {
+        TimeRange::Day => "qdr:d",
+        TimeRange::Week => "qdr:w",
+        TimeRange::Month => "qdr:m",
+        TimeRange::Year => "qdr:y",
+    }
+}
+
+fn safe_search_value(safe_search: SafeSearch) -> &'static str {
+    match safe_search {
+        SafeSearch::Off => "off",
+        SafeSearch::Moderate => "medium",
+        SafeSearch::Strict => "high",
+    }
+}
+
+fn generate_async_value(start: usize) -> String {
     // https://github.com/searxng/searxng/blob/08a90d46d6f23607ddecf2a2d9fa216df69d2fac/searx/engines/google.py#L80
 
     let use_ac = "use_ac:true";
@@ -50,11 +79,7 @@ fn generate_async_value() -> String {
         *arc_id = (generate_new_arc_id_random(), Instant::now());
     }
 
-    let page_number = 1;
-    let arc_id = format!(
-        "arc_id:srp_{random_characters}_{skip}",
-        skip = 100 + page_number * 10
-    );
+    let arc_id = format!("arc_id:srp_{random_characters}_{skip}", skip = 100 + start);
 
     format!("{arc_id},{use_ac},{fmt}")
 }
@@ -138,29 +163,29 @@ fn recursive_iter_featured_snippet_children(description: &mut String, el: &Eleme
             scraper::Node::Text(t) => {
                 description.push_str(&t.text);
             }
-            scraper::Node::Element(inner_el) => {
+            scraper::Node::Element(inner_el)
                 if inner_el.attr("data-ved").is_none()
-                    || inner_el.attr("data-send-open-event").is_some()
-                {
-                    recursive_iter_featured_snippet_children(
-                        description,
-                        &ElementRef::wrap(inner_node).unwrap(),
-                    );
-                }
+                    || inner_el.attr("data-send-open-event").is_some() =>
+            {
+                recursive_iter_featured_snippet_children(
+                    description,
+                    &ElementRef::wrap(inner_node).unwrap(),
+                );
             }
             _ => {}
         }
     }
 }
 
-pub fn request_autocomplete(query: &str) -> reqwest::RequestBuilder {
+pub fn request_autocomplete(query: &str, config: &Config) -> reqwest::RequestBuilder {
     CLIENT.get(
         Url::parse_with_params(
             "https://suggestqueries.google.com/complete/search",
             &[
                 ("output", "firefox"),
                 ("client", "firefox"),
-                ("hl", "US-en"),
+                ("hl", &config.language),
+                ("gl", &config.region.to_lowercase()),
                 ("q", query),
             ],
         )
@@ -182,12 +207,20 @@ pub fn parse_autocomplete_response(body: &str) -> eyre::Result<Vec<String>> {
         .collect())
 }
 
-pub fn request_images(query: &str) -> reqwest::RequestBuilder {
+pub fn request_images(query: &SearchQuery) -> reqwest::RequestBuilder {
     // ok so google also has a json api for images BUT it gives us less results
     CLIENT.get(
         Url::parse_with_params(
             "https://www.google.com/search",
-            &[("q", query), ("udm", "2"), ("prmd", "ivsnmbtz")],
+            &[
+                ("q", query.engine_query(&Engine::Google).as_str()),
+                ("udm", "2"),
+                ("prmd", "ivsnmbtz"),
+                ("start", &query.offset(100).to_string()),
+                ("hl", &query.config.language),
+                ("gl", &query.config.region.to_lowercase()),
+                ("safe", safe_search_value(query.config.safe_search)),
+            ],
         )
         .unwrap(),
     )
@@ -268,6 +301,75 @@ pub fn parse_images_response(body: &str) -> eyre::Result<EngineImagesResponse> {
     Ok(EngineImagesResponse { image_results })
 }
 
+pub fn request_news(query: &SearchQuery) -> reqwest::RequestBuilder {
+    let mut url = Url::parse_with_params(
+        "https://www.google.com/search",
+        &[
+            ("q", query.engine_query(&Engine::Google).as_str()),
+            ("tbm", "nws"),
+            ("start", &query.offset(10).to_string()),
+            ("hl", &query.config.language),
+            ("gl", &query.config.region.to_lowercase()),
+            ("safe", safe_search_value(query.config.safe_search)),
+        ],
+    )
+    .unwrap();
+    if let Some(time_range) = query.time_range {
+        url.query_pairs_mut()
+            .append_pair("tbs", time_range_value(time_range));
+    }
+    CLIENT.get(url)
+}
+
+pub fn parse_news_response(body: &str) -> eyre::Result<EngineNewsResponse> {
+    let dom = scraper::Html::parse_document(body);
+
+    let result_sel = Selector::parse("div.SoaBEf").unwrap();
+    let href_sel = Selector::parse("a[href]").unwrap();
+    let title_sel = Selector::parse("div[role='heading']").unwrap();
+    let description_sel = Selector::parse(".GI74Re").unwrap();
+    let source_sel = Selector::parse(".MgUUmf").unwrap();
+    let published_sel = Selector::parse(".OSrXXb, .LfVVr").unwrap();
+    let thumbnail_sel = Selector::parse("img[src^='http']").unwrap();
+
+    let text = |el: &ElementRef, sel: &Selector| {
+        el.select(sel)
+            .next()
+            .map(|el| el.text().collect::<String>().trim().to_string())
+    };
+
+    let mut news_results = Vec::new();
+    for result_el in dom.select(&result_sel) {
+        let Some(url) = result_el
+            .select(&href_sel)
+            .next()
+            .and_then(|el| el.value().attr("href"))
+        else {
+            warn!("couldn't get url for google news result");
+            continue;
+        };
+        let Some(title) = text(&result_el, &title_sel) else {
+            warn!("couldn't get title for google news result");
+            continue;
+        };
+
+        news_results.push(EngineNewsResult {
+            url: clean_url(url)?,
+            title,
+            description: text(&result_el, &description_sel).unwrap_or_default(),
+            source: text(&result_el, &source_sel).unwrap_or_default(),
+            published: text(&result_el, &published_sel).and_then(|t| parse_published(&t)),
+            thumbnail: result_el
+                .select(&thumbnail_sel)
+                .next()
+                .and_then(|el| el.value().attr("src"))
+                .map(str::to_string),
+        });
+    }
+
+    Ok(EngineNewsResponse { news_results })
+}
+
 fn clean_url(url: &str) -> eyre::Result<String> {
     if url.starts_with("/url?q=") {
         // get the q param
@@ -1,16 +1,22 @@
 use reqwest::Url;
 
 use crate::{
-    engines::{EngineResponse, RequestResponse, CLIENT},
+    engines::{Engine, EngineResponse, RequestResponse, SearchQuery, CLIENT},
     parse::{parse_html_response_with_opts, ParseOpts},
 };
 
-pub fn request(query: &str) -> RequestResponse {
+pub fn request(query: &SearchQuery) -> RequestResponse {
     CLIENT
         .get(
             Url::parse_with_params(
                 "https://scholar.google.com/scholar",
-                &[("hl", "en"), ("as_sdt", "0,5"), ("q", query), ("btnG", "")],
+                &[
+                    ("hl", query.config.language.as_str()),
+                    ("as_sdt", "0,5"),
+                    ("q", query.engine_query(&Engine::GoogleScholar).as_str()),
+                    ("btnG", ""),
+                    ("start", &query.offset(10).to_string()),
+                ],
             )
             .unwrap(),
         )
@@ -0,0 +1,196 @@
+//! Video search with the API of an Invidious or Piped instance, which are
+//! alternative frontends for YouTube.
+
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use url::Url;
+
+use crate::engines::{
+    Engine, EngineVideoResult, EngineVideosResponse, HttpResponse, RequestResponse, SearchQuery,
+    TimeRange, CLIENT,
+};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct InvidiousConfig {
+    /// The instance that's used for searching, like `https://inv.nadeko.net`.
+    pub instance_url: String,
+    pub api: InvidiousApi,
+}
+
+impl Default for InvidiousConfig {
+    fn default() -> Self {
+        Self {
+            instance_url: "https://inv.nadeko.net".to_string(),
+            api: InvidiousApi::Invidious,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InvidiousApi {
+    #[default]
+    Invidious,
+    Piped,
+}
+
+pub fn request_videos(query: &SearchQuery) -> RequestResponse {
+    let config = InvidiousConfig::get(&query.config);
+    let instance_url = config.instance_url.trim_end_matches('/');
+    let search_query = query.engine_query(&Engine::Invidious);
+
+    let url = match config.api {
+        InvidiousApi::Invidious => {
+            let mut url = Url::parse_with_params(
+                &format!("{instance_url}/api/v1/search"),
+                &[
+                    ("q", search_query.as_str()),
+                    ("type", "video"),
+                    ("page", &query.page.to_string()),
+                    ("region", &query.config.region),
+                ],
+            );
+            if let (Ok(url), Some(time_range)) = (&mut url, query.time_range) {
+                let date = match time_range {
+                    TimeRange::Day => "today",
+                    TimeRange::Week => "week",
+                    TimeRange::Month => "month",
+                    TimeRange::Year => "year",
+                };
+                url.query_pairs_mut().append_pair("date", date);
+            }
+            url
+        }
+        InvidiousApi::Piped => {
+            // piped paginates with a token from the previous page, so we only get the
+            // first one
+            if query.page > 1 {
+                return RequestResponse::None;
+            }
+            Url::parse_with_params(
+                &format!("{instance_url}/search"),
+                &[("q", search_query.as_str()), ("filter", "videos")],
+            )
+        }
+    };
+
+    match url {
+        Ok(url) => CLIENT.get(url).into(),
+        Err(err) => {
+            error!("invalid Invidious instance url {instance_url:?}: {err}");
+            RequestResponse::None
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InvidiousVideo {
+    #[serde(rename = "type")]
+    item_type: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    video_id: String,
+    #[serde(default)]
+    author: String,
+    #[serde(default)]
+    description: String,
+    /// Unix timestamp in seconds.
+    published: Option<i64>,
+    length_seconds: Option<u64>,
+    #[serde(default)]
+    video_thumbnails: Vec<InvidiousThumbnail>,
+}
+
+#[derive(Deserialize)]
+struct InvidiousThumbnail {
+    quality: String,
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct PipedSearch {
+    items: Vec<PipedVideo>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PipedVideo {
+    #[serde(rename = "type")]
+    item_type: String,
+    /// Like `/watch?v=dQw4w9WgXcQ`.
+    #[serde(default)]
+    url: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    uploader_name: String,
+    short_description: Option<String>,
+    /// Unix timestamp in milliseconds, or -1 if it's unknown.
+    uploaded: Option<i64>,
+    /// In seconds, or -1 for livestreams.
+    duration: Option<i64>,
+    thumbnail: Option<String>,
+}
+
+pub fn parse_videos_response(res: &HttpResponse) -> eyre::Result<EngineVideosResponse> {
+    let config = InvidiousConfig::get(&res.config);
+    let instance_url = config.instance_url.trim_end_matches('/');
+    // instances can have relative thumbnail urls
+    let absolute_url = |url: String| {
+        if url.starts_with('/') {
+            format!("{instance_url}{url}")
+        } else {
+            url
+        }
+    };
+
+    // results link to youtube so they're merged with results from other engines.
+    // urls.replace in the config can be used to link to an instance instead.
+    let video_results = match config.api {
+        InvidiousApi::Invidious => serde_json::from_str::<Vec<InvidiousVideo>>(&res.body)?
+            .into_iter()
+            .filter(|video| video.item_type == "video")
+            .map(|video| EngineVideoResult {
+                url: format!("https://www.youtube.com/watch?v={}", video.video_id),
+                title: video.title,
+                description: video.description,
+                channel: video.author,
+                duration: video.length_seconds.filter(|&s| s > 0),
+                published: video
+                    .published
+                    .and_then(|published| DateTime::from_timestamp(published, 0)),
+                thumbnail: video
+                    .video_thumbnails
+                    .into_iter()
+                    .find(|thumbnail| thumbnail.quality == "medium")
+                    .map(|thumbnail| absolute_url(thumbnail.url)),
+            })
+            .collect(),
+        InvidiousApi::Piped => serde_json::from_str::<PipedSearch>(&res.body)?
+            .items
+            .into_iter()
+            .filter(|video| video.item_type == "stream")
+            .map(|video| EngineVideoResult {
+                url: format!("https://www.youtube.com{}", video.url),
+                title: video.title,
+                description: video.short_description.unwrap_or_default(),
+                channel: video.uploader_name,
+                duration: video
+                    .duration
+                    .and_then(|duration| u64::try_from(duration).ok())
+                    .filter(|&s| s > 0),
+                published: video
+                    .uploaded
+                    .filter(|&uploaded| uploaded > 0)
+                    .and_then(DateTime::from_timestamp_millis),
+                thumbnail: video.thumbnail.map(absolute_url),
+            })
+            .collect(),
+    };
+
+    Ok(EngineVideosResponse { video_results })
+}
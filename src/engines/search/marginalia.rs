@@ -1,49 +1,56 @@
 use reqwest::Url;
-use serde::Deserialize;
-use tracing::error;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     engines::{Engine, EngineResponse, RequestResponse, SearchQuery, CLIENT},
     parse::{parse_html_response_with_opts, ParseOpts},
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct MarginaliaConfig {
     pub args: MarginaliaArgs,
 }
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
 pub struct MarginaliaArgs {
     pub profile: String,
     pub js: String,
     pub adtech: String,
 }
 
+impl Default for MarginaliaArgs {
+    fn default() -> Self {
+        Self {
+            profile: "corpo".to_string(),
+            js: "default".to_string(),
+            adtech: "default".to_string(),
+        }
+    }
+}
+
 pub fn request(query: &SearchQuery) -> RequestResponse {
-    // if the query is more than 3 words or has any special characters then abort
-    if query.split_whitespace().count() > 3
-        || !query.chars().all(|c| c.is_ascii_alphanumeric() || c == ' ')
+    // if the query is more than 3 words or has any special characters then abort.
+    // operators are fine since marginalia understands them.
+    let words = query.parsed.words();
+    if words.split_whitespace().count() > 3
+        || !words.chars().all(|c| c.is_ascii_alphanumeric() || c == ' ')
     {
         return RequestResponse::None;
     }
 
-    let config_toml = query.config.engines.get(Engine::Marginalia).extra.clone();
-    let config: MarginaliaConfig = match toml::Value::Table(config_toml).try_into() {
-        Ok(args) => args,
-        Err(err) => {
-            error!("Failed to parse Marginalia config: {err}");
-            return RequestResponse::None;
-        }
-    };
+    let config = MarginaliaConfig::get(&query.config);
 
     CLIENT
         .get(
             Url::parse_with_params(
                 "https://search.marginalia.nu/search",
                 &[
-                    ("query", query.query.as_str()),
+                    ("query", query.engine_query(&Engine::Marginalia).as_str()),
                     ("profile", config.args.profile.as_str()),
                     ("js", config.args.js.as_str()),
                     ("adtech", config.args.adtech.as_str()),
+                    ("page", &query.page.to_string()),
                 ],
             )
             .unwrap(),
@@ -1,13 +1,24 @@
 use reqwest::Url;
 
 use crate::{
-    engines::{EngineResponse, RequestResponse, CLIENT},
+    engines::{Engine, EngineResponse, RequestResponse, SearchQuery, CLIENT},
     parse::{parse_html_response_with_opts, ParseOpts},
 };
 
-pub fn request(query: &str) -> RequestResponse {
+pub fn request(query: &SearchQuery) -> RequestResponse {
+    // rightdao doesn't have pagination
+    if query.page > 1 {
+        return RequestResponse::None;
+    }
+
     CLIENT
-        .get(Url::parse_with_params("https://rightdao.com/search", &[("q", query)]).unwrap())
+        .get(
+            Url::parse_with_params(
+                "https://rightdao.com/search",
+                &[("q", query.engine_query(&Engine::RightDao).as_str())],
+            )
+            .unwrap(),
+        )
         .into()
 }
 
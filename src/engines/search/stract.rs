@@ -1,22 +1,33 @@
 use reqwest::Url;
 
 use crate::{
-    engines::{EngineResponse, RequestResponse, CLIENT},
+    config::SafeSearch,
+    engines::{Engine, EngineResponse, RequestResponse, SearchQuery, CLIENT},
     parse::{parse_html_response_with_opts, ParseOpts},
 };
 
-pub fn request(query: &str) -> RequestResponse {
+pub fn request(query: &SearchQuery) -> RequestResponse {
     CLIENT
         .get(
             Url::parse_with_params(
                 "https://stract.com/search",
                 &[
-                    ("ss", "false"),
+                    // stract's safe search is either on or off
+                    (
+                        "ss",
+                        if query.config.safe_search == SafeSearch::Off {
+                            "false"
+                        } else {
+                            "true"
+                        },
+                    ),
                     // this is not a tracking parameter or token
                     // this is stract's default value for the search rankings parameter
                     ("sr", "N4IgNglg1gpgJiAXAbQLoBoRwgZ0rBFDEAIzAHsBjApNAXyA"),
-                    ("q", query),
+                    ("q", query.engine_query(&Engine::Stract).as_str()),
                     ("optic", ""),
+                    // stract's pages start at 0
+                    ("p", &query.offset(1).to_string()),
                 ],
             )
             .unwrap(),
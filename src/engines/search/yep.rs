@@ -1,19 +1,26 @@
 use reqwest::Url;
 use serde::Deserialize;
 
-use crate::engines::{EngineResponse, EngineSearchResult, RequestResponse, CLIENT};
+use crate::engines::{
+    Engine, EngineResponse, EngineSearchResult, RequestResponse, SearchQuery, CLIENT,
+};
+
+pub fn request(query: &SearchQuery) -> RequestResponse {
+    // yep's api doesn't let us get more than the first page
+    if query.page > 1 {
+        return RequestResponse::None;
+    }
 
-pub fn request(query: &str) -> RequestResponse {
     CLIENT
         .get(
             Url::parse_with_params(
                 "https://api.yep.com/fs/2/search",
                 &[
                     ("client", "web"),
-                    ("gl", "all"),
+                    ("gl", &query.config.region),
                     ("no_correct", "true"),
-                    ("q", query),
-                    ("safeSearch", "off"),
+                    ("q", query.engine_query(&Engine::Yep).as_str()),
+                    ("safeSearch", query.config.safe_search.as_str()),
                     ("type", "web"),
                 ],
             )
@@ -1,11 +1,14 @@
 use std::{
-    env,
+    env, io,
     path::{Path, PathBuf},
+    process,
 };
 
 use config::Config;
+use futures::FutureExt;
 use tracing::error;
 
+pub mod cli;
 pub mod config;
 pub mod engines;
 pub mod parse;
@@ -14,14 +17,37 @@ pub mod web;
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
-    tracing_subscriber::fmt::init();
+    let args = env::args().skip(1).collect::<Vec<_>>();
 
-    if env::args().any(|arg| arg == "--help" || arg == "-h" || arg == "help" || arg == "h") {
-        println!("Usage: metasearch [config_path]");
+    if args.iter().any(|arg| arg == "--help" || arg == "-h")
+        || matches!(args.first().map(String::as_str), Some("help" | "h"))
+    {
+        println!("{USAGE}");
         return;
     }
 
-    let config_path = config_path();
+    let subcommand = match args.first().map(String::as_str) {
+        Some("check-engines") => Some(cli::check_engines::run(args[1..].to_vec()).boxed()),
+        Some("search") => Some(cli::search::run(args[1..].to_vec()).boxed()),
+        Some("config") => Some(cli::config::run(args[1..].to_vec()).boxed()),
+        _ => None,
+    };
+    if let Some(subcommand) = subcommand {
+        // the output of subcommands can be piped somewhere, so logs go to stderr
+        tracing_subscriber::fmt().with_writer(io::stderr).init();
+        match subcommand.await {
+            Ok(true) => return,
+            Ok(false) => process::exit(1),
+            Err(err) => {
+                eprintln!("{err}");
+                process::exit(2);
+            }
+        }
+    }
+
+    tracing_subscriber::fmt::init();
+
+    let config_path = config_path(args.first().map(PathBuf::from));
     let config = match Config::read_or_create(&config_path) {
         Ok(config) => config,
         Err(err) => {
@@ -29,12 +55,20 @@ async fn main() {
             return;
         }
     };
-    web::run(config).await;
+    web::run(config, config_path).await;
 }
 
-fn config_path() -> PathBuf {
-    if let Some(config_path) = env::args().nth(1) {
-        return PathBuf::from(config_path);
+const USAGE: &str = "\
+Usage: metasearch [config_path]
+       metasearch check-engines [--engine <id>]... [--fixtures <dir>] [--format table|json] [--config <path>]
+       metasearch search [--tab all|images|news|videos] [--format text|json] [--config <path>] [--] <query>
+       metasearch config check [--config <path>]
+       metasearch config dump [--show-secrets] [--config <path>]";
+
+/// Find the config, preferring the path that was passed on the command line.
+fn config_path(config_path: Option<PathBuf>) -> PathBuf {
+    if let Some(config_path) = config_path {
+        return config_path;
     }
 
     let app_name = env!("CARGO_PKG_NAME");
@@ -5,54 +5,55 @@ use crate::{
     urls::normalize_url,
 };
 
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use scraper::{Html, Selector};
 use tracing::trace;
 
 #[derive(Default)]
-pub struct ParseOpts {
-    result: &'static str,
-    title: QueryMethod,
-    href: QueryMethod,
-    description: QueryMethod,
-
-    featured_snippet: &'static str,
-    featured_snippet_title: QueryMethod,
-    featured_snippet_href: QueryMethod,
-    featured_snippet_description: QueryMethod,
+pub struct ParseOpts<'a> {
+    result: &'a str,
+    title: QueryMethod<'a>,
+    href: QueryMethod<'a>,
+    description: QueryMethod<'a>,
+
+    featured_snippet: &'a str,
+    featured_snippet_title: QueryMethod<'a>,
+    featured_snippet_href: QueryMethod<'a>,
+    featured_snippet_description: QueryMethod<'a>,
 }
 
-impl ParseOpts {
+impl<'a> ParseOpts<'a> {
     #[must_use]
     pub fn new() -> Self {
         Self::default()
     }
 
     #[must_use]
-    pub fn result(mut self, result: &'static str) -> Self {
+    pub fn result(mut self, result: &'a str) -> Self {
         self.result = result;
         self
     }
 
     #[must_use]
-    pub fn title(mut self, title: impl Into<QueryMethod>) -> Self {
+    pub fn title(mut self, title: impl Into<QueryMethod<'a>>) -> Self {
         self.title = title.into();
         self
     }
 
     #[must_use]
-    pub fn href(mut self, href: impl Into<QueryMethod>) -> Self {
+    pub fn href(mut self, href: impl Into<QueryMethod<'a>>) -> Self {
         self.href = href.into();
         self
     }
 
     #[must_use]
-    pub fn description(mut self, description: impl Into<QueryMethod>) -> Self {
+    pub fn description(mut self, description: impl Into<QueryMethod<'a>>) -> Self {
         self.description = description.into();
         self
     }
 
     #[must_use]
-    pub fn featured_snippet(mut self, featured_snippet: &'static str) -> Self {
+    pub fn featured_snippet(mut self, featured_snippet: &'a str) -> Self {
         self.featured_snippet = featured_snippet;
         self
     }
@@ -60,14 +61,17 @@ impl ParseOpts {
     #[must_use]
     pub fn featured_snippet_title(
         mut self,
-        featured_snippet_title: impl Into<QueryMethod>,
+        featured_snippet_title: impl Into<QueryMethod<'a>>,
     ) -> Self {
         self.featured_snippet_title = featured_snippet_title.into();
         self
     }
 
     #[must_use]
-    pub fn featured_snippet_href(mut self, featured_snippet_href: impl Into<QueryMethod>) -> Self {
+    pub fn featured_snippet_href(
+        mut self,
+        featured_snippet_href: impl Into<QueryMethod<'a>>,
+    ) -> Self {
         self.featured_snippet_href = featured_snippet_href.into();
         self
     }
@@ -75,7 +79,7 @@ impl ParseOpts {
     #[must_use]
     pub fn featured_snippet_description(
         mut self,
-        featured_snippet_description: impl Into<QueryMethod>,
+        featured_snippet_description: impl Into<QueryMethod<'a>>,
     ) -> Self {
         self.featured_snippet_description = featured_snippet_description.into();
         self
@@ -85,24 +89,24 @@ impl ParseOpts {
 type ManualQueryMethod = Box<dyn Fn(&scraper::ElementRef) -> eyre::Result<String>>;
 
 #[derive(Default)]
-pub enum QueryMethod {
+pub enum QueryMethod<'a> {
     #[default]
     None,
-    CssSelector(&'static str),
+    CssSelector(&'a str),
     Manual(ManualQueryMethod),
 }
 
-impl From<&'static str> for QueryMethod {
-    fn from(s: &'static str) -> Self {
+impl<'a> From<&'a str> for QueryMethod<'a> {
+    fn from(s: &'a str) -> Self {
         QueryMethod::CssSelector(s)
     }
 }
 
-impl QueryMethod {
+impl QueryMethod<'_> {
     pub fn call_with_css_selector_override(
         &self,
         el: &scraper::ElementRef,
-        with_css_selector: impl Fn(&scraper::ElementRef, &'static str) -> Option<String>,
+        with_css_selector: impl Fn(&scraper::ElementRef, &str) -> Option<String>,
     ) -> eyre::Result<String> {
         match self {
             QueryMethod::None => Ok(String::new()),
@@ -122,7 +126,7 @@ impl QueryMethod {
 
 pub(super) fn parse_html_response_with_opts(
     body: &str,
-    opts: ParseOpts,
+    opts: ParseOpts<'_>,
 ) -> eyre::Result<EngineResponse> {
     let dom = Html::parse_document(body);
 
@@ -212,3 +216,125 @@ pub(super) fn parse_html_response_with_opts(
         infobox_html: None,
     })
 }
+
+/// Parse the time that a news article was published, as it's shown by search
+/// engines. This can be relative like "3 hours ago" or "3h", or a date like
+/// "Jan 5, 2024".
+#[must_use]
+pub fn parse_published(text: &str) -> Option<DateTime<Utc>> {
+    parse_published_at(text, Utc::now())
+}
+
+fn parse_published_at(text: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let text = text.trim().trim_end_matches('.');
+
+    if let Ok(date) = DateTime::parse_from_rfc3339(text) {
+        return Some(date.to_utc());
+    }
+    for format in ["%b %d, %Y", "%B %d, %Y", "%d %b %Y", "%d %B %Y", "%Y-%m-%d"] {
+        if let Ok(date) = NaiveDate::parse_from_str(text, format) {
+            return Some(date.and_hms_opt(0, 0, 0)?.and_utc());
+        }
+    }
+
+    let text = text.to_lowercase();
+    let text = text.strip_suffix(" ago").unwrap_or(&text).trim();
+    let (amount, unit) = match text.split_once(' ') {
+        // "3 hours"
+        Some((amount, unit)) => (amount, unit),
+        // "3h"
+        None => text.split_at(text.find(|c: char| !c.is_ascii_digit())?),
+    };
+    let amount = match amount {
+        "a" | "an" => 1,
+        _ => amount.parse::<i64>().ok()?,
+    };
+    // "hours" and "hour" are the same, but "s" is seconds
+    let unit = if unit.len() > 1 {
+        unit.trim_end_matches('s')
+    } else {
+        unit
+    };
+    // the amount comes from the engine, so it could be too big for a duration or
+    // a date
+    let duration = match unit {
+        "s" | "sec" | "second" => Duration::try_seconds(amount),
+        "m" | "min" | "minute" => Duration::try_minutes(amount),
+        "h" | "hr" | "hour" => Duration::try_hours(amount),
+        "d" | "day" => Duration::try_days(amount),
+        "w" | "wk" | "week" => Duration::try_weeks(amount),
+        "mo" | "mon" | "month" => Duration::try_days(amount.checked_mul(30)?),
+        "y" | "yr" | "year" => Duration::try_days(amount.checked_mul(365)?),
+        _ => return None,
+    }?;
+    now.checked_sub_signed(duration)
+}
+
+/// Parse the length of a video like "1:02:03" or "4:05" into seconds.
+#[must_use]
+pub fn parse_duration(text: &str) -> Option<u64> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let mut seconds = 0u64;
+    for part in text.split(':') {
+        seconds = seconds
+            .checked_mul(60)?
+            .checked_add(part.parse::<u64>().ok()?)?;
+    }
+    Some(seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_published() {
+        let now = DateTime::parse_from_rfc3339("2024-03-10T12:00:00Z")
+            .unwrap()
+            .to_utc();
+        let ago = |duration| Some(now - duration);
+
+        assert_eq!(
+            parse_published_at("3 hours ago", now),
+            ago(Duration::hours(3))
+        );
+        assert_eq!(
+            parse_published_at("an hour ago", now),
+            ago(Duration::hours(1))
+        );
+        assert_eq!(parse_published_at("1 day ago", now), ago(Duration::days(1)));
+        assert_eq!(parse_published_at("45m", now), ago(Duration::minutes(45)));
+        assert_eq!(parse_published_at("2d", now), ago(Duration::days(2)));
+        assert_eq!(parse_published_at("1mo", now), ago(Duration::days(30)));
+        assert_eq!(
+            parse_published_at("Jan 5, 2024", now),
+            Some(
+                DateTime::parse_from_rfc3339("2024-01-05T00:00:00Z")
+                    .unwrap()
+                    .to_utc()
+            )
+        );
+        assert_eq!(parse_published_at("yesterday-ish", now), None);
+        assert_eq!(parse_published_at("", now), None);
+        // too far in the past to be a date
+        assert_eq!(parse_published_at("9999999 years ago", now), None);
+        assert_eq!(
+            parse_published_at(&format!("{} years ago", i64::MAX), now),
+            None
+        );
+        assert_eq!(parse_published_at(&format!("{}s", i64::MAX), now), None);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("4:05"), Some(245));
+        assert_eq!(parse_duration("1:02:03"), Some(3723));
+        assert_eq!(parse_duration(" 59 "), Some(59));
+        assert_eq!(parse_duration("live"), None);
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("18446744073709551615:00"), None);
+    }
+}
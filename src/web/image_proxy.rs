@@ -61,6 +61,8 @@ pub async fn route(
         }
     }
 
+    engines::metrics::record_image_proxy(image_bytes.len());
+
     (
         [
             (axum::http::header::CONTENT_TYPE, content_type),
@@ -73,3 +75,27 @@ pub async fn route(
     )
         .into_response()
 }
+
+/// The url that an image should be loaded from, which goes through the image
+/// proxy if it's enabled.
+#[must_use]
+pub fn proxied_url(url: &str, config: &Config) -> String {
+    if config.image_search.proxy.enabled {
+        let escaped_param =
+            url::form_urlencoded::byte_serialize(url.as_bytes()).collect::<String>();
+        format!("/image-proxy?url={escaped_param}")
+    } else {
+        url.to_string()
+    }
+}
+
+/// The url that a thumbnail should be loaded from. The image proxy only works
+/// when image search is enabled, so thumbnails that would've been proxied
+/// aren't shown otherwise.
+#[must_use]
+pub fn thumbnail_url(url: &str, config: &Config) -> Option<String> {
+    if config.image_search.proxy.enabled && !config.image_search.enabled {
+        return None;
+    }
+    Some(proxied_url(url, config))
+}
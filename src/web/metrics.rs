@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
+};
+
+use crate::{
+    config::Config,
+    engines,
+    web::{reload::SharedConfig, status::has_status_token},
+};
+
+pub async fn route(
+    State(base_config): State<SharedConfig>,
+    Extension(config): Extension<Config>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Response {
+    if !config.metrics {
+        return (StatusCode::FORBIDDEN, "Metrics are disabled").into_response();
+    }
+    // the metrics use the same token as the status page
+    if !has_status_token(&base_config.get(), &params, &headers) {
+        return (StatusCode::UNAUTHORIZED, "Missing or incorrect token").into_response();
+    }
+
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        engines::metrics::render(&config),
+    )
+        .into_response()
+}
@@ -1,11 +1,14 @@
 mod autocomplete;
 mod image_proxy;
 mod index;
+mod metrics;
 mod opensearch;
-mod search;
+mod reload;
+pub mod search;
 mod settings;
+mod status;
 
-use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+use std::{convert::Infallible, net::SocketAddr, path::PathBuf};
 
 use axum::{
     extract::{Request, State},
@@ -20,6 +23,7 @@ use maud::{html, Markup, PreEscaped};
 use tracing::info;
 
 use crate::config::Config;
+use reload::SharedConfig;
 
 macro_rules! register_static_routes {
     ( $app:ident, $( $x:expr ),* ) => {
@@ -39,10 +43,12 @@ macro_rules! register_static_routes {
     };
 }
 
-pub async fn run(config: Config) {
+pub async fn run(config: Config, config_path: PathBuf) {
     let bind_addr = config.bind;
 
-    let config = Arc::new(config);
+    status::start_uptime();
+    let config = SharedConfig::new(config);
+    reload::watch(config.clone(), config_path);
 
     fn static_route<S>(
         content: &'static str,
@@ -63,6 +69,9 @@ pub async fn run(config: Config) {
         .route("/opensearch.xml", get(opensearch::route))
         .route("/autocomplete", get(autocomplete::route))
         .route("/image-proxy", get(image_proxy::route))
+        .route("/metrics", get(metrics::route))
+        .route("/status", get(status::route))
+        .route("/healthz", get(status::healthz))
         .layer(middleware::from_fn_with_state(
             config.clone(),
             config_middleware,
@@ -102,18 +111,17 @@ fn guess_mime_type(path: &str) -> &'static str {
 }
 
 async fn config_middleware(
-    State(config): State<Arc<Config>>,
+    State(config): State<SharedConfig>,
     cookies: CookieJar,
     mut req: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    let mut config = config.clone().as_ref().clone();
+    let mut config = config.get().as_ref().clone();
 
     let settings_cookie = cookies.get("settings");
     if let Some(settings_cookie) = settings_cookie {
         if let Ok(settings) = serde_json::from_str::<settings::Settings>(settings_cookie.value()) {
-            config.ui.stylesheet_url = settings.stylesheet_url;
-            config.ui.stylesheet_str = settings.stylesheet_str;
+            settings.apply(&mut config);
         }
     }
 
@@ -0,0 +1,104 @@
+//! Reloading the config while the server is running, when the file changes or
+//! when we get a SIGHUP.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use parking_lot::RwLock;
+use tracing::{error, info, warn};
+
+use crate::config::Config;
+
+/// How often the config file is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The config that the server is currently using. Searches that are already
+/// running keep the config that they started with when it's replaced.
+#[derive(Clone)]
+pub struct SharedConfig(Arc<RwLock<Arc<Config>>>);
+
+impl SharedConfig {
+    #[must_use]
+    pub fn new(config: Config) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(config))))
+    }
+
+    #[must_use]
+    pub fn get(&self) -> Arc<Config> {
+        self.0.read().clone()
+    }
+
+    fn set(&self, config: Config) {
+        *self.0.write() = Arc::new(config);
+    }
+
+    /// Read the config file again and use it if it's valid. If it isn't, the
+    /// error is logged and the old config is kept.
+    fn reload(&self, config_path: &Path) {
+        let new_config = match Config::read(config_path) {
+            Ok(config) => config,
+            Err(err) => {
+                error!("Couldn't reload config, keeping the old one:\n{err}");
+                return;
+            }
+        };
+
+        let old_config = self.get();
+        if new_config.bind != old_config.bind {
+            warn!(
+                "The bind address can't be changed without restarting, still listening on {}",
+                old_config.bind
+            );
+        }
+
+        self.set(new_config);
+        info!("Reloaded config from {config_path:?}");
+    }
+}
+
+/// Reload the config whenever the file is modified or the process gets a
+/// SIGHUP. This runs until the server stops.
+pub fn watch(config: SharedConfig, config_path: PathBuf) {
+    #[cfg(unix)]
+    {
+        let config = config.clone();
+        let config_path = config_path.clone();
+        tokio::spawn(async move {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            let mut hangup = match signal(SignalKind::hangup()) {
+                Ok(hangup) => hangup,
+                Err(err) => {
+                    error!("Couldn't listen for SIGHUP: {err}");
+                    return;
+                }
+            };
+            while hangup.recv().await.is_some() {
+                info!("Got SIGHUP, reloading config");
+                config.reload(&config_path);
+            }
+        });
+    }
+
+    tokio::spawn(async move {
+        let mut last_modified = modified_time(&config_path);
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            let modified = modified_time(&config_path);
+            // the file might be missing for a moment while an editor saves it
+            if modified.is_some() && modified != last_modified {
+                last_modified = modified;
+                config.reload(&config_path);
+            }
+        }
+    });
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
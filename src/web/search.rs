@@ -1,5 +1,8 @@
 mod all;
+mod bangs;
 mod images;
+mod news;
+mod videos;
 
 use std::{collections::HashMap, net::SocketAddr, str::FromStr};
 
@@ -12,40 +15,100 @@ use axum::{
     Extension, Json,
 };
 use bytes::Bytes;
+use chrono::{DateTime, Utc};
 use maud::{html, PreEscaped, DOCTYPE};
+use serde::Serialize;
 
 use crate::{
     config::Config,
     engines::{
-        self, Engine, EngineProgressUpdate, ProgressUpdateData, ResponseForTab, SearchQuery,
-        SearchTab,
+        self, operators::ParsedQuery, Engine, EngineProgressUpdate, EngineSelection,
+        ProgressUpdateData, ResponseForTab, SearchQuery, SearchTab, TimeRange,
     },
     web::head_html,
 };
 
-fn render_beginning_of_html(search: &SearchQuery) -> String {
+/// The parameters of a search that links to other tabs and pages of the
+/// results should keep.
+#[derive(Debug, Clone)]
+pub struct SearchParams {
+    pub query: String,
+    /// The language from the `lang` parameter, which overrides the user's
+    /// settings.
+    pub lang: Option<String>,
+    pub time_range: Option<TimeRange>,
+    pub engine_selection: EngineSelection,
+}
+
+impl SearchParams {
+    #[must_use]
+    pub fn href(&self, tab: SearchTab, page: usize) -> String {
+        let mut params = url::form_urlencoded::Serializer::new(String::new());
+        params.append_pair("q", &self.query);
+        if tab != SearchTab::default() {
+            params.append_pair("tab", &tab.to_string());
+        }
+        if page > 1 {
+            params.append_pair("page", &page.to_string());
+        }
+        if let Some(lang) = &self.lang {
+            params.append_pair("lang", lang);
+        }
+        // time ranges don't apply to images
+        if let Some(time_range) = self.time_range.filter(|_| tab.has_time_range()) {
+            params.append_pair("time_range", &time_range.to_string());
+        }
+        if !self.engine_selection.is_empty() {
+            params.append_pair("engines", &self.engine_selection.to_string());
+        }
+        format!("?{}", params.finish())
+    }
+}
+
+fn render_beginning_of_html(search: &SearchQuery, params: &SearchParams) -> String {
     let form_html = html! {
         form.search-form action="/search" method="get" {
             input #search-input  type="text" name="q" placeholder="Search" value=(search.query) autofocus onfocus="this.select()" autocomplete="off";
             @if search.tab != SearchTab::default() {
                 input type="hidden" name="tab" value=(search.tab.to_string());
             }
+            @if let Some(lang) = &params.lang {
+                input type="hidden" name="lang" value=(lang);
+            }
+            @if !params.engine_selection.is_empty() {
+                input type="hidden" name="engines" value=(params.engine_selection.to_string());
+            }
+            @if search.tab.has_time_range() {
+                select.time-range-select name="time_range" aria-label="Time range" onchange="this.form.submit()" {
+                    option value="" { "Any time" }
+                    @for (time_range, name) in TimeRange::ALL.iter().zip(["Past day", "Past week", "Past month", "Past year"]) {
+                        option value=(time_range.to_string()) selected[search.time_range == Some(*time_range)] { (name) }
+                    }
+                }
+            }
             input type="submit" value="Search";
         }
-        @if search.config.image_search.enabled {
-            div.search-tabs {
-                @if search.tab == SearchTab::All { span.search-tab.selected { "All" } }
-                @else { a.search-tab href={ "?q=" (search.query) } { "All" } }
+        div.search-tabs {
+            @if search.tab == SearchTab::All { span.search-tab.selected { "All" } }
+            @else { a.search-tab href=(params.href(SearchTab::All, 1)) { "All" } }
+            @if search.config.image_search.enabled {
                 @if search.tab == SearchTab::Images { span.search-tab.selected { "Images" } }
-                @else { a.search-tab href={ "?q=" (search.query) "&tab=images" } { "Images" } }
+                @else { a.search-tab href=(params.href(SearchTab::Images, 1)) { "Images" } }
             }
+            @if search.tab == SearchTab::News { span.search-tab.selected { "News" } }
+            @else { a.search-tab href=(params.href(SearchTab::News, 1)) { "News" } }
+            @if search.tab == SearchTab::Videos { span.search-tab.selected { "Videos" } }
+            @else { a.search-tab href=(params.href(SearchTab::Videos, 1)) { "Videos" } }
+        }
+        @if !params.engine_selection.is_empty() {
+            (render_engine_selection(params, search.tab))
         }
     };
 
     // we don't close the elements here because we do chunked responses
     html! {
         (DOCTYPE)
-        html lang="en";
+        html lang=(search.config.language);
         {(head_html(Some(&search.query), &search.config))}
         body;
         div.main-container.{"search-" (search.tab.to_string())};
@@ -56,19 +119,106 @@ fn render_beginning_of_html(search: &SearchQuery) -> String {
     .into_string()
 }
 
+/// Show which engines were chosen for the query, with a link to search with
+/// every engine instead.
+fn render_engine_selection(params: &SearchParams, tab: SearchTab) -> PreEscaped<String> {
+    let selection = &params.engine_selection;
+    let engine_ids = |engines: &[Engine]| {
+        engines
+            .iter()
+            .map(|engine| engine.id())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let without_selection = SearchParams {
+        engine_selection: EngineSelection::default(),
+        ..params.clone()
+    };
+
+    html! {
+        p.engine-selection {
+            @if !selection.unmatched.is_empty() {
+                "No matching enabled engines for " (selection.unmatched.join(", ")) ". "
+            }
+            @if let Some(only) = selection.only.as_ref().filter(|only| !only.is_empty()) {
+                "Only searching with " (engine_ids(only)) ". "
+            }
+            @if !selection.exclude.is_empty() {
+                "Not searching with " (engine_ids(&selection.exclude)) ". "
+            }
+            a href=(without_selection.href(tab, 1)) { "Use all engines" }
+        }
+    }
+}
+
 fn render_end_of_html() -> String {
     r"</main></div></body></html>".to_string()
 }
 
-fn render_results_for_tab(response: ResponseForTab) -> PreEscaped<String> {
+fn render_results_for_tab(response: ResponseForTab, params: &SearchParams) -> PreEscaped<String> {
     match response {
-        ResponseForTab::All(r) => all::render_results(r),
-        ResponseForTab::Images(r) => images::render_results(r),
+        ResponseForTab::All(r) => all::render_results(r, params),
+        ResponseForTab::Images(r) => images::render_results(r, params),
+        ResponseForTab::News(r) => news::render_results(r, params),
+        ResponseForTab::Videos(r) => videos::render_results(r, params),
+    }
+}
+
+/// Render the links to the previous and next pages of results.
+pub fn render_pagination(
+    params: &SearchParams,
+    tab: SearchTab,
+    page: usize,
+    has_next_page: bool,
+) -> PreEscaped<String> {
+    let page_href = |page: usize| params.href(tab, page);
+    let has_next_page = has_next_page && page < engines::MAX_PAGE;
+
+    html! {
+        @if page > 1 || has_next_page {
+            nav.pagination {
+                @if page > 1 {
+                    a.pagination-previous href=(page_href(page - 1)) { "Previous" }
+                }
+                span.pagination-current { "Page " (page) }
+                @if has_next_page {
+                    a.pagination-next href=(page_href(page + 1)) { "Next" }
+                }
+            }
+        }
+    }
+}
+
+/// Show how long ago an article was published, like "3 hours ago".
+pub fn format_age(published: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let age = now - published;
+    let (amount, unit) = if age.num_minutes() < 1 {
+        return "just now".to_string();
+    } else if age.num_hours() < 1 {
+        (age.num_minutes(), "minute")
+    } else if age.num_days() < 1 {
+        (age.num_hours(), "hour")
+    } else if age.num_days() < 31 {
+        (age.num_days(), "day")
+    } else {
+        return published.format("%b %-d, %Y").to_string();
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+    format!("{amount} {unit}{plural} ago")
+}
+
+/// Format the length of a video like "1:02:03" or "4:05".
+pub fn format_duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
     }
 }
 
 fn render_engine_progress_update(
-    engine: Engine,
+    engine: &Engine,
     progress_update: &EngineProgressUpdate,
     time_ms: u64,
 ) -> String {
@@ -80,6 +230,11 @@ fn render_engine_progress_update(
         EngineProgressUpdate::Error(msg) => {
             html! { span.progress-update-error { (msg) } }.into_string()
         }
+        EngineProgressUpdate::Suspended => {
+            html! { span.progress-update-error { "suspended after repeated failures" } }
+                .into_string()
+        }
+        EngineProgressUpdate::NoTimeRange => "skipped, can't filter by time".to_string(),
     };
 
     html! {
@@ -118,16 +273,24 @@ pub fn render_engine_list(engines: &[engines::Engine], config: &Config) -> PreEs
 
 pub async fn get(
     Query(params): Query<HashMap<String, String>>,
-    Extension(config): Extension<Config>,
+    Extension(mut config): Extension<Config>,
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
 ) -> axum::response::Response {
-    let query = params
+    let mut query = params
         .get("q")
         .cloned()
         .unwrap_or_default()
         .trim()
         .replace('\n', " ");
+    let go_to_first_result = match bangs::strip_first_result_prefix(&query) {
+        Some(rest) => {
+            query = rest.to_string();
+            true
+        }
+        None => false,
+    };
+    let (query, engines_prefix) = strip_engines_prefix(&query);
     if query.is_empty() {
         // redirect to index
         return (
@@ -141,14 +304,59 @@ pub async fn get(
             .into_response();
     }
 
-    let search_tab = params
-        .get("tab")
-        .and_then(|t| SearchTab::from_str(t).ok())
-        .unwrap_or_default();
+    if !go_to_first_result {
+        if let Some(url) = bangs::redirect_url(&query, &config) {
+            return redirect(&url);
+        }
+    }
+
+    let search_tab = if go_to_first_result {
+        SearchTab::All
+    } else {
+        params
+            .get("tab")
+            .and_then(|t| SearchTab::from_str(t).ok())
+            .unwrap_or_default()
+    };
+
+    let page = params
+        .get("page")
+        .and_then(|p| p.parse::<usize>().ok())
+        .map_or(1, |p| p.clamp(1, engines::MAX_PAGE));
+
+    // invalid languages are ignored, and aren't kept in links
+    let lang = params
+        .get("lang")
+        .filter(|lang| config.set_locale(lang))
+        .cloned();
+    let time_range = params
+        .get("time_range")
+        .and_then(|t| TimeRange::from_str(t).ok())
+        .filter(|_| search_tab.has_time_range());
+    // the engines can be chosen with the engines parameter or with a prefix in
+    // the query, and both are used if they're both there
+    let engine_selection = EngineSelection::parse(
+        &[params.get("engines").cloned(), engines_prefix]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(","),
+        &config,
+    );
+    let search_params = SearchParams {
+        query: query.clone(),
+        lang,
+        time_range,
+        engine_selection: engine_selection.clone(),
+    };
 
     let query = SearchQuery {
+        parsed: ParsedQuery::parse(&query),
         query,
         tab: search_tab,
+        page,
+        time_range,
+        engine_selection,
         request_headers: headers
             .clone()
             .into_iter()
@@ -170,6 +378,20 @@ pub async fn get(
         config: config.clone().into(),
     };
 
+    if go_to_first_result {
+        let search_url = format!("/search{}", search_params.href(SearchTab::All, 1));
+        let results = match search_to_completion(query).await {
+            Ok(results) => results,
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        };
+        let first_result_url = results.into_iter().find_map(|r| match r {
+            ResponseForTab::All(r) => r.search_results.into_iter().next().map(|r| r.result.url),
+            ResponseForTab::Images(_) | ResponseForTab::News(_) | ResponseForTab::Videos(_) => None,
+        });
+        // show the normal results page if there weren't any results
+        return redirect(first_result_url.as_deref().unwrap_or(&search_url));
+    }
+
     let trying_to_use_api =
         query.request_headers.get("accept") == Some(&"application/json".to_string());
     if trying_to_use_api {
@@ -177,20 +399,16 @@ pub async fn get(
             return (StatusCode::FORBIDDEN, "API access is disabled").into_response();
         }
 
-        let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
-        let search_future = tokio::spawn(async move { engines::search(&query, progress_tx).await });
-        if let Err(e) = search_future.await {
-            return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
-        }
-
-        let mut results = Vec::new();
-        while let Some(progress_update) = progress_rx.recv().await {
-            if let ProgressUpdateData::Response(r) = progress_update.data {
-                results.push(r);
-            }
-        }
-
-        return Json(results).into_response();
+        return match search_to_completion(query).await {
+            Ok(results) => Json(
+                results
+                    .into_iter()
+                    .map(|response| ApiResponse::new(response, &search_params))
+                    .collect::<Vec<_>>(),
+            )
+            .into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        };
     }
 
     let s = stream! {
@@ -202,7 +420,7 @@ pub async fn get(
         // 2) the results
         // 3) the post-search infobox (usually not sent) + the end of the html
 
-        let first_part = render_beginning_of_html(&query);
+        let first_part = render_beginning_of_html(&query, &search_params);
         // second part is in the loop
         let mut third_part = String::new();
 
@@ -217,17 +435,29 @@ pub async fn get(
                 ProgressUpdateData::Engine { engine, update } => {
                     let progress_html = format!(
                         r#"<p class="progress-update">{}</p>"#,
-                        render_engine_progress_update(engine, &update, progress_update.time_ms)
+                        render_engine_progress_update(&engine, &update, progress_update.time_ms)
                     );
                     yield R::Ok(Bytes::from(progress_html));
                 },
+                ProgressUpdateData::CacheHit => {
+                    let progress_html = html! {
+                        p.progress-update {
+                            span.progress-update-time {
+                                (format!("{:>4}", progress_update.time_ms))
+                                "ms"
+                            }
+                            " using cached results"
+                        }
+                    }.into_string();
+                    yield R::Ok(Bytes::from(progress_html));
+                },
                 ProgressUpdateData::Response(results) => {
                     let mut second_part = String::new();
 
                     second_part.push_str("</div>"); // close progress-updates
                     #[allow(clippy::literal_string_with_formatting_args)]
                     second_part.push_str("<style>.progress-updates{display:none}</style>");
-                    second_part.push_str(&render_results_for_tab(results).into_string());
+                    second_part.push_str(&render_results_for_tab(*results, &search_params).into_string());
                     yield Ok(Bytes::from(second_part));
                 },
                 ProgressUpdateData::PostSearchInfobox(infobox) => {
@@ -264,3 +494,74 @@ pub async fn get(
     )
         .into_response()
 }
+
+/// A response from the JSON API, which has links to the other pages of
+/// results.
+#[derive(Serialize)]
+struct ApiResponse {
+    #[serde(flatten)]
+    response: ResponseForTab,
+    previous_page: Option<String>,
+    next_page: Option<String>,
+}
+
+impl ApiResponse {
+    fn new(response: ResponseForTab, params: &SearchParams) -> Self {
+        let tab = match response {
+            ResponseForTab::All(_) => SearchTab::All,
+            ResponseForTab::Images(_) => SearchTab::Images,
+            ResponseForTab::News(_) => SearchTab::News,
+            ResponseForTab::Videos(_) => SearchTab::Videos,
+        };
+        let page = response.page();
+        let page_url = |page: usize| format!("/search{}", params.href(tab, page));
+        Self {
+            previous_page: (page > 1).then(|| page_url(page - 1)),
+            next_page: response.has_next_page().then(|| page_url(page + 1)),
+            response,
+        }
+    }
+}
+
+/// Do the search without streaming the progress updates, and return the
+/// responses.
+async fn search_to_completion(query: SearchQuery) -> eyre::Result<Vec<ResponseForTab>> {
+    let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+    let search_future = tokio::spawn(async move { engines::search(&query, progress_tx).await });
+    search_future.await??;
+
+    let mut results = Vec::new();
+    while let Some(progress_update) = progress_rx.recv().await {
+        if let ProgressUpdateData::Response(r) = progress_update.data {
+            results.push(*r);
+        }
+    }
+    Ok(results)
+}
+
+/// Remove a word like `engines:google,-bing` from the query, and return the
+/// query without it and the engines that were in it.
+pub fn strip_engines_prefix(query: &str) -> (String, Option<String>) {
+    let mut engines = None;
+    let rest = query
+        .split_whitespace()
+        .filter(|word| {
+            match word
+                .get(.."engines:".len())
+                .filter(|prefix| prefix.eq_ignore_ascii_case("engines:"))
+            {
+                Some(prefix) if engines.is_none() => {
+                    engines = Some(word[prefix.len()..].to_string());
+                    false
+                }
+                _ => true,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    (rest, engines)
+}
+
+fn redirect(url: &str) -> axum::response::Response {
+    (StatusCode::FOUND, [(header::LOCATION, url)]).into_response()
+}
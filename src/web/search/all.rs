@@ -1,21 +1,23 @@
 //! Rendering results in the "all" tab.
 
+use std::slice;
+
 use maud::{html, PreEscaped};
 
 use crate::{
     config::Config,
-    engines::{self, EngineSearchResult, Infobox, Response},
-    web::search::render_engine_list,
+    engines::{self, EngineSearchResult, Infobox, Response, SearchTab},
+    web::search::{render_engine_list, render_pagination, SearchParams},
 };
 
-pub fn render_results(response: Response) -> PreEscaped<String> {
+pub fn render_results(response: Response, params: &SearchParams) -> PreEscaped<String> {
     let mut html = String::new();
     if let Some(answer) = &response.answer {
         html.push_str(
             &html! {
                 div.answer {
                     (answer.html)
-                    (render_engine_list(&[answer.engine], &response.config))
+                    (render_engine_list(slice::from_ref(&answer.engine), &response.config))
                 }
             }
             .into_string(),
@@ -26,7 +28,7 @@ pub fn render_results(response: Response) -> PreEscaped<String> {
             &html! {
                 div.infobox {
                     (infobox.html)
-                    (render_engine_list(&[infobox.engine], &response.config))
+                    (render_engine_list(slice::from_ref(&infobox.engine), &response.config))
                 }
             }
             .into_string(),
@@ -48,6 +50,16 @@ pub fn render_results(response: Response) -> PreEscaped<String> {
         );
     }
 
+    html.push_str(
+        &render_pagination(
+            params,
+            SearchTab::All,
+            response.page,
+            !response.search_results.is_empty(),
+        )
+        .into_string(),
+    );
+
     PreEscaped(html)
 }
 
@@ -62,7 +74,7 @@ fn render_search_result(
                 h3.search-result-title { (result.result.title) }
             }
             p.search-result-description { (result.result.description) }
-            (render_engine_list(&result.engines.iter().copied().collect::<Vec<_>>(), config))
+            (render_engine_list(&result.engines.iter().cloned().collect::<Vec<_>>(), config))
         }
     }
 }
@@ -78,7 +90,7 @@ fn render_featured_snippet(
                 span.search-result-url { (featured_snippet.url) }
                 h3.search-result-title { (featured_snippet.title) }
             }
-            (render_engine_list(&[featured_snippet.engine], config))
+            (render_engine_list(slice::from_ref(&featured_snippet.engine), config))
         }
     }
 }
@@ -87,7 +99,7 @@ pub fn render_infobox(infobox: &Infobox, config: &Config) -> PreEscaped<String>
     html! {
         div.infobox.postsearch-infobox {
             (infobox.html)
-            (render_engine_list(&[infobox.engine], &config))
+            (render_engine_list(slice::from_ref(&infobox.engine), config))
         }
     }
 }
@@ -0,0 +1,43 @@
+//! DuckDuckGo-style bangs like `!w rust`, which redirect to another site's
+//! search instead of searching with metasearch.
+
+use url::Url;
+
+use crate::config::Config;
+
+/// If the query has a bang that's in the config, get the url that it should
+/// redirect to.
+pub fn redirect_url(query: &str, config: &Config) -> Option<String> {
+    // the bang can be anywhere in the query, like on duckduckgo
+    let (bang_index, url_template) = query
+        .split_whitespace()
+        .enumerate()
+        .filter_map(|(i, word)| Some((i, word.strip_prefix('!')?)))
+        .find_map(|(i, bang)| Some((i, config.bangs.get(&bang.to_lowercase())?)))?;
+
+    let rest = query
+        .split_whitespace()
+        .enumerate()
+        .filter(|&(i, _)| i != bang_index)
+        .map(|(_, word)| word)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if rest.is_empty() {
+        // a bang without a query goes to the site's homepage
+        if let Ok(url) = Url::parse(url_template) {
+            return Some(url.origin().ascii_serialization());
+        }
+    }
+
+    Some(url_template.replace("{query}", &urlencoding::encode(&rest)))
+}
+
+/// If the query starts with `\` or `!!`, returns the rest of the query. These
+/// redirect to the first result.
+pub fn strip_first_result_prefix(query: &str) -> Option<&str> {
+    query
+        .strip_prefix('\\')
+        .or_else(|| query.strip_prefix("!!"))
+        .map(str::trim)
+}
@@ -2,17 +2,21 @@ use maud::{html, PreEscaped};
 
 use crate::{
     config::Config,
-    engines::{self, EngineImageResult, ImagesResponse},
-    web::search::render_engine_list,
+    engines::{self, EngineImageResult, ImagesResponse, SearchTab},
+    web::{
+        image_proxy,
+        search::{render_engine_list, render_pagination, SearchParams},
+    },
 };
 
-pub fn render_results(response: ImagesResponse) -> PreEscaped<String> {
+pub fn render_results(response: ImagesResponse, params: &SearchParams) -> PreEscaped<String> {
     html! {
         div.image-results {
             @for image in &response.image_results {
                 (render_image_result(image, &response.config))
             }
         }
+        (render_pagination(params, SearchTab::Images, response.page, !response.image_results.is_empty()))
     }
 }
 
@@ -21,14 +25,7 @@ fn render_image_result(
     config: &Config,
 ) -> PreEscaped<String> {
     let original_image_src = &result.result.image_url;
-    let image_src = if config.image_search.proxy.enabled {
-        // serialize url params
-        let escaped_param =
-            url::form_urlencoded::byte_serialize(original_image_src.as_bytes()).collect::<String>();
-        format!("/image-proxy?url={}", escaped_param)
-    } else {
-        original_image_src.to_string()
-    };
+    let image_src = image_proxy::proxied_url(original_image_src, config);
     html! {
         div.image-result {
             a.image-result-anchor rel="noreferrer" href=(original_image_src) target="_blank" {
@@ -41,7 +38,7 @@ fn render_image_result(
                 span.image-result-title { (result.result.title) }
             }
             @if config.image_search.show_engines {
-                {(render_engine_list(&result.engines.iter().copied().collect::<Vec<_>>(), &config))}
+                {(render_engine_list(&result.engines.iter().cloned().collect::<Vec<_>>(), config))}
             }
         }
     }
@@ -0,0 +1,59 @@
+//! Rendering results in the "news" tab.
+
+use chrono::{DateTime, Utc};
+use maud::{html, PreEscaped};
+
+use crate::{
+    config::Config,
+    engines::{self, EngineNewsResult, NewsResponse, SearchTab},
+    web::{
+        image_proxy,
+        search::{format_age, render_engine_list, render_pagination, SearchParams},
+    },
+};
+
+pub fn render_results(response: NewsResponse, params: &SearchParams) -> PreEscaped<String> {
+    let now = Utc::now();
+    html! {
+        @if response.news_results.is_empty() {
+            p { "No results." }
+        }
+        @for result in &response.news_results {
+            (render_news_result(result, &response.config, now))
+        }
+        (render_pagination(params, SearchTab::News, response.page, !response.news_results.is_empty()))
+    }
+}
+
+fn render_news_result(
+    result: &engines::SearchResult<EngineNewsResult>,
+    config: &Config,
+    now: DateTime<Utc>,
+) -> PreEscaped<String> {
+    let news_result = &result.result;
+    html! {
+        div.search-result.news-result {
+            @if let Some(thumbnail) = news_result.thumbnail.as_ref().and_then(|t| image_proxy::thumbnail_url(t, config)) {
+                img.news-result-thumbnail loading="lazy" src=(thumbnail) alt="";
+            }
+            div.news-result-content {
+                a.search-result-anchor rel="noreferrer" href=(news_result.url) {
+                    span.search-result-url { (news_result.url) }
+                    h3.search-result-title { (news_result.title) }
+                }
+                p.news-result-info {
+                    @if !news_result.source.is_empty() {
+                        span.news-result-source { (news_result.source) }
+                    }
+                    @if let Some(published) = news_result.published {
+                        time.news-result-published datetime=(published.to_rfc3339()) title=(published.format("%Y-%m-%d %H:%M UTC")) {
+                            (format_age(published, now))
+                        }
+                    }
+                }
+                p.search-result-description { (news_result.description) }
+                (render_engine_list(&result.engines.iter().cloned().collect::<Vec<_>>(), config))
+            }
+        }
+    }
+}
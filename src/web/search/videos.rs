@@ -0,0 +1,66 @@
+//! Rendering results in the "videos" tab.
+
+use chrono::{DateTime, Utc};
+use maud::{html, PreEscaped};
+
+use crate::{
+    config::Config,
+    engines::{self, EngineVideoResult, SearchTab, VideosResponse},
+    web::{
+        image_proxy,
+        search::{
+            format_age, format_duration, render_engine_list, render_pagination, SearchParams,
+        },
+    },
+};
+
+pub fn render_results(response: VideosResponse, params: &SearchParams) -> PreEscaped<String> {
+    let now = Utc::now();
+    html! {
+        @if response.video_results.is_empty() {
+            p { "No results." }
+        }
+        @for result in &response.video_results {
+            (render_video_result(result, &response.config, now))
+        }
+        (render_pagination(params, SearchTab::Videos, response.page, !response.video_results.is_empty()))
+    }
+}
+
+fn render_video_result(
+    result: &engines::SearchResult<EngineVideoResult>,
+    config: &Config,
+    now: DateTime<Utc>,
+) -> PreEscaped<String> {
+    let video_result = &result.result;
+    html! {
+        div.search-result.video-result {
+            a.video-result-thumbnail-anchor rel="noreferrer" href=(video_result.url) {
+                @if let Some(thumbnail) = video_result.thumbnail.as_ref().and_then(|t| image_proxy::thumbnail_url(t, config)) {
+                    img.video-result-thumbnail loading="lazy" src=(thumbnail) alt="";
+                }
+                @if let Some(duration) = video_result.duration {
+                    span.video-result-duration { (format_duration(duration)) }
+                }
+            }
+            div.video-result-content {
+                a.search-result-anchor rel="noreferrer" href=(video_result.url) {
+                    span.search-result-url { (video_result.url) }
+                    h3.search-result-title { (video_result.title) }
+                }
+                p.video-result-info {
+                    @if !video_result.channel.is_empty() {
+                        span.video-result-channel { (video_result.channel) }
+                    }
+                    @if let Some(published) = video_result.published {
+                        time.video-result-published datetime=(published.to_rfc3339()) title=(published.format("%Y-%m-%d %H:%M UTC")) {
+                            (format_age(published, now))
+                        }
+                    }
+                }
+                p.search-result-description { (video_result.description) }
+                (render_engine_list(&result.engines.iter().cloned().collect::<Vec<_>>(), config))
+            }
+        }
+    }
+}
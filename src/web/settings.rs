@@ -1,4 +1,7 @@
+use std::{collections::BTreeMap, sync::Arc};
+
 use axum::{
+    extract::State,
     http::{header, StatusCode},
     response::IntoResponse,
     Extension, Form,
@@ -7,9 +10,17 @@ use axum_extra::extract::{cookie::Cookie, CookieJar};
 use maud::{html, Markup, PreEscaped, DOCTYPE};
 use serde::{Deserialize, Serialize};
 
-use crate::{config::Config, web::head_html};
+use crate::{
+    config::{Config, SafeSearch},
+    engines::Engine,
+    web::{head_html, reload::SharedConfig},
+};
 
-pub async fn get(Extension(config): Extension<Config>) -> impl IntoResponse {
+pub async fn get(
+    State(base_config): State<SharedConfig>,
+    Extension(config): Extension<Config>,
+) -> impl IntoResponse {
+    let base_config = base_config.get();
     let theme_option = |value: &str, name: &str| -> Markup {
         let selected = config.ui.stylesheet_url == value;
         html! {
@@ -42,6 +53,53 @@ pub async fn get(Extension(config): Extension<Config>) -> impl IntoResponse {
 
                             br;
 
+                            label for="safe-search" { "Safe search" }
+                            select #safe-search name="safe-search" {
+                                // the operator can stop users from turning safe search off
+                                @for (safe_search, name) in SafeSearch::ALL.into_iter().zip(["Off", "Moderate", "Strict"]).filter(|(s, _)| *s >= config.min_safe_search) {
+                                    option value=(safe_search.as_str()) selected[config.safe_search == safe_search] {
+                                        { (name) }
+                                    }
+                                }
+                            }
+
+                            br;
+
+                            label for="language" { "Language" }
+                            input #language type="text" name="language" value=(config.language) pattern="[a-zA-Z]{2,3}" size="3";
+                            label for="region" { "Region" }
+                            input #region type="text" name="region" value=(config.region) pattern="[a-zA-Z0-9]{2,3}" size="3";
+
+                            br;
+
+                            // users can only turn image search off if the operator enabled it
+                            @if base_config.image_search.enabled {
+                                label for="image-search" { "Image search" }
+                                input #image-search type="checkbox" name="image-search" checked[config.image_search.enabled];
+
+                                br;
+                            }
+
+                            details #engine-settings-details {
+                                summary { "Engines" }
+                                table.engine-settings {
+                                    tr {
+                                        th { "Engine" }
+                                        th { "Enabled" }
+                                        th { "Weight" }
+                                    }
+                                    // users can only turn engines off if the operator enabled them
+                                    @for engine in Engine::all_with_custom(&config).into_iter().filter(|engine| base_config.engines.get(engine).enabled) {
+                                        @let engine_config = config.engines.get(&engine);
+                                        tr {
+                                            td { label for=(format!("engine-{engine}-enabled")) { (engine) } }
+                                            td { input #(format!("engine-{engine}-enabled")) type="checkbox" name=(format!("engine-{engine}-enabled")) checked[engine_config.enabled]; }
+                                            td { input type="number" name=(format!("engine-{engine}-weight")) value=(engine_config.weight) min="0" step="any" aria-label=(format!("{engine} weight")); }
+                                        }
+                                    }
+                                }
+                            }
+
                             // custom css textarea
                             details #custom-css-details {
                                 summary { "Custom CSS" }
@@ -67,9 +125,117 @@ pub async fn get(Extension(config): Extension<Config>) -> impl IntoResponse {
 pub struct Settings {
     pub stylesheet_url: String,
     pub stylesheet_str: String,
+    // optional so settings cookies from before this was added still work
+    #[serde(default)]
+    pub safe_search: Option<SafeSearch>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Whether to show the images tab. This can only turn image search off.
+    #[serde(default)]
+    pub image_search: Option<bool>,
+    /// The engines that the user changed, keyed by their ids. Engines that
+    /// aren't here use the instance's config.
+    #[serde(default)]
+    pub engines: BTreeMap<String, EngineSettings>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct EngineSettings {
+    pub enabled: bool,
+    pub weight: f64,
+}
+
+impl Settings {
+    /// Get the settings from the submitted form. `config` is the instance's
+    /// config without the user's settings, which is used to only save the
+    /// engine settings that are different from the defaults.
+    fn from_form(form: &[(String, String)], config: &Config) -> Self {
+        let get = |name: &str| {
+            form.iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+
+        let mut engines = BTreeMap::new();
+        for engine in Engine::all_with_custom(config) {
+            // unchecked checkboxes aren't submitted, so we check for the weight to know
+            // whether the engine was in the form
+            let Some(weight) = get(&format!("engine-{engine}-weight")) else {
+                continue;
+            };
+            let engine_config = config.engines.get(&engine);
+            let enabled = get(&format!("engine-{engine}-enabled")).is_some();
+            let weight = weight
+                .parse::<f64>()
+                .ok()
+                .filter(|weight| weight.is_finite() && *weight >= 0.)
+                .unwrap_or(engine_config.weight);
+            // only the changed engines are saved so changes to the instance's config
+            // still apply to the others
+            if enabled != engine_config.enabled || weight != engine_config.weight {
+                engines.insert(engine.id().to_string(), EngineSettings { enabled, weight });
+            }
+        }
+
+        Self {
+            stylesheet_url: get("stylesheet-url").unwrap_or_default(),
+            stylesheet_str: get("stylesheet-str").unwrap_or_default(),
+            safe_search: get("safe-search")
+                .and_then(|value| SafeSearch::ALL.into_iter().find(|s| s.as_str() == value)),
+            language: get("language"),
+            region: get("region"),
+            image_search: config
+                .image_search
+                .enabled
+                .then(|| get("image-search").is_some()),
+            engines,
+        }
+    }
+
+    /// Apply the settings to the config, ignoring the ones that the instance
+    /// doesn't allow.
+    pub fn apply(self, config: &mut Config) {
+        config.ui.stylesheet_url = self.stylesheet_url;
+        config.ui.stylesheet_str = self.stylesheet_str;
+        if let Some(safe_search) = self.safe_search {
+            config.safe_search = safe_search.max(config.min_safe_search);
+        }
+        // invalid languages and regions are ignored
+        if let Some(language) = self.language {
+            config.set_locale(&language);
+        }
+        if let Some(region) = self.region {
+            config.set_locale(&format!("{}-{region}", config.language));
+        }
+        if let Some(image_search) = self.image_search {
+            config.image_search.enabled &= image_search;
+        }
+
+        if !self.engines.is_empty() {
+            let mut engines_config = config.engines.as_ref().clone();
+            for engine in Engine::all_with_custom(config) {
+                let Some(engine_settings) = self.engines.get(engine.id()) else {
+                    continue;
+                };
+                let engine_config = engines_config.map.entry(engine).or_default();
+                engine_config.enabled &= engine_settings.enabled;
+                if engine_settings.weight.is_finite() && engine_settings.weight >= 0. {
+                    engine_config.weight = engine_settings.weight;
+                }
+            }
+            config.engines = Arc::new(engines_config);
+        }
+    }
 }
 
-pub async fn post(mut jar: CookieJar, Form(settings): Form<Settings>) -> impl IntoResponse {
+pub async fn post(
+    State(base_config): State<SharedConfig>,
+    mut jar: CookieJar,
+    Form(form): Form<Vec<(String, String)>>,
+) -> impl IntoResponse {
+    let settings = Settings::from_form(&form, &base_config.get());
     let mut settings_cookie = Cookie::new("settings", serde_json::to_string(&settings).unwrap());
     settings_cookie.make_permanent();
     jar = jar.add(settings_cookie);
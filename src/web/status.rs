@@ -0,0 +1,213 @@
+//! `/status`, a page for whoever runs the instance that shows how the engines
+//! are doing, and `/healthz` for load balancers.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use chrono::Utc;
+use maud::{html, DOCTYPE};
+use serde::Serialize;
+
+use crate::{
+    config::Config,
+    engines::health::{self, CircuitState, EngineHealthReport},
+    web::{
+        head_html,
+        index::{BASE_COMMIT_URL, COMMIT_HASH, COMMIT_HASH_SHORT, VERSION},
+        reload::SharedConfig,
+        search::format_age,
+    },
+};
+
+static START_TIME: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+/// Start counting the uptime from now.
+pub fn start_uptime() {
+    LazyLock::force(&START_TIME);
+}
+
+/// Always succeeds, since the server only starts listening once the config
+/// has been loaded.
+pub async fn healthz() -> &'static str {
+    "ok"
+}
+
+#[derive(Serialize)]
+struct Status<'a> {
+    version: &'static str,
+    commit: &'static str,
+    uptime_secs: u64,
+    engines: BTreeMap<&'a str, &'a EngineHealthReport>,
+}
+
+pub async fn route(
+    State(base_config): State<SharedConfig>,
+    Extension(config): Extension<Config>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Response {
+    // the user's settings shouldn't affect which engines are shown as enabled
+    let base_config = base_config.get();
+    if !has_status_token(&base_config, &params, &headers) {
+        return (StatusCode::UNAUTHORIZED, "Missing or incorrect token").into_response();
+    }
+
+    let report = health::report(&base_config);
+    let uptime = START_TIME.elapsed();
+
+    if headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        == Some("application/json")
+    {
+        return Json(Status {
+            version: VERSION,
+            commit: COMMIT_HASH,
+            uptime_secs: uptime.as_secs(),
+            engines: report
+                .iter()
+                .map(|(engine, report)| (engine.id(), report))
+                .collect(),
+        })
+        .into_response();
+    }
+
+    let now = Utc::now();
+    let html = html! {
+        (DOCTYPE)
+        html lang="en" {
+            {(head_html(Some("status"), &config))}
+            body {
+                div.main-container.status-page {
+                    main {
+                        a.back-to-index-button href="/" { "Back" }
+                        h1 { "Status" }
+                        p {
+                            "Version " (VERSION)
+                            @if COMMIT_HASH != "unknown" && COMMIT_HASH_SHORT != "unknown" {
+                                " ("
+                                a href=(format!("{BASE_COMMIT_URL}{COMMIT_HASH}")) { (COMMIT_HASH_SHORT) }
+                                ")"
+                            }
+                            ", up for " (format_uptime(uptime))
+                        }
+                        table.status-engines {
+                            thead {
+                                tr {
+                                    th { "Engine" }
+                                    th { "Enabled" }
+                                    th { "State" }
+                                    th { "Success rate" }
+                                    th { "Median latency" }
+                                    th { "Last error" }
+                                }
+                            }
+                            tbody {
+                                @for (engine, report) in &report {
+                                    tr.disabled[!report.enabled] {
+                                        td { (engine) }
+                                        td { @if report.enabled { "yes" } @else { "no" } }
+                                        td { (match report.state {
+                                            CircuitState::Closed => "ok",
+                                            CircuitState::Open => "suspended",
+                                            CircuitState::HalfOpen => "probing",
+                                        }) }
+                                        td {
+                                            @if let Some(success_rate) = report.recent_success_rate {
+                                                (format!("{:.0}%", success_rate * 100.))
+                                                span.status-detail { " of " (report.recent_requests) }
+                                            } @else {
+                                                "-"
+                                            }
+                                        }
+                                        td {
+                                            @if let Some(median_latency_ms) = report.median_latency_ms {
+                                                (median_latency_ms) "ms"
+                                            } @else {
+                                                "-"
+                                            }
+                                        }
+                                        td {
+                                            @if let Some(last_error) = &report.last_error {
+                                                (last_error.message)
+                                                span.status-detail { " (" (format_age(last_error.time, now)) ")" }
+                                            } @else {
+                                                "-"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    .into_string();
+
+    (
+        [
+            (header::CONTENT_TYPE, "text/html; charset=utf-8"),
+            // the page can have a token in its url
+            (header::REFERRER_POLICY, "no-referrer"),
+        ],
+        html,
+    )
+        .into_response()
+}
+
+/// Whether the request has the `status_token` from the config, either in the
+/// `token` parameter or an `Authorization: Bearer` header. This is always true
+/// if there's no token.
+pub fn has_status_token(
+    config: &Config,
+    params: &HashMap<String, String>,
+    headers: &HeaderMap,
+) -> bool {
+    let Some(status_token) = config
+        .status_token
+        .as_deref()
+        .filter(|token| !token.is_empty())
+    else {
+        return true;
+    };
+    let token = params.get("token").map(String::as_str).or_else(|| {
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+    });
+    token.is_some_and(|token| tokens_match(token, status_token))
+}
+
+/// Compare the tokens in constant time, so the token can't be guessed from
+/// how long the comparison takes.
+fn tokens_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn format_uptime(uptime: Duration) -> String {
+    let secs = uptime.as_secs();
+    let (days, hours, minutes) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60);
+    if days > 0 {
+        format!("{days}d {hours}h {minutes}m")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else {
+        format!("{minutes}m")
+    }
+}